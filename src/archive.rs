@@ -0,0 +1,157 @@
+//! Persistent record of processed videos for resumable batch runs.
+//!
+//! Like yt-dlp's `--download-archive`, but it also remembers the tracks each
+//! video produced, so a video whose split was cut short only redoes the
+//! missing chapters.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// What the archive knows about one video.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VideoRecord {
+    /// URL the video was processed from
+    pub url: Option<String>,
+    /// Every track was written
+    pub complete: bool,
+    /// Tracks written so far
+    pub tracks: Vec<PathBuf>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct ArchiveData {
+    videos: BTreeMap<String, VideoRecord>,
+}
+
+/// A JSON archive file, saved after every change.
+#[derive(Debug)]
+pub struct Archive {
+    path: PathBuf,
+    data: Mutex<ArchiveData>,
+}
+
+impl Archive {
+    /// Loads the archive at `path`; a missing file is an empty archive.
+    ///
+    /// # Errors
+    /// Fails when the file exists but cannot be read or parsed.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let data = if path.exists() {
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read archive {}", path.display()))?;
+            serde_json::from_str(&content)
+                .with_context(|| format!("Invalid archive {}", path.display()))?
+        } else {
+            ArchiveData::default()
+        };
+        Ok(Self {
+            path,
+            data: Mutex::new(data),
+        })
+    }
+
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The record of video `id`, if any.
+    #[must_use]
+    pub fn get(&self, id: &str) -> Option<VideoRecord> {
+        self.lock().videos.get(id).cloned()
+    }
+
+    /// Whether a video processed from `url` is complete.
+    #[must_use]
+    pub fn is_complete_url(&self, url: &str) -> bool {
+        self.lock()
+            .videos
+            .values()
+            .any(|v| v.complete && v.url.as_deref() == Some(url))
+    }
+
+    /// Notes that video `id` is being processed from `url`.
+    ///
+    /// # Errors
+    /// Fails when the archive cannot be saved.
+    pub fn start(&self, id: &str, url: &str) -> Result<()> {
+        self.update(id, |record| record.url = Some(url.to_string()))
+    }
+
+    /// Adds finished `tracks` to video `id`.
+    ///
+    /// # Errors
+    /// Fails when the archive cannot be saved.
+    pub fn add_tracks(&self, id: &str, tracks: &[PathBuf]) -> Result<()> {
+        self.update(id, |record| {
+            for track in tracks {
+                if !record.tracks.contains(track) {
+                    record.tracks.push(track.clone());
+                }
+            }
+        })
+    }
+
+    /// Marks video `id` complete.
+    ///
+    /// # Errors
+    /// Fails when the archive cannot be saved.
+    pub fn complete(&self, id: &str) -> Result<()> {
+        self.update(id, |record| record.complete = true)
+    }
+
+    fn update(&self, id: &str, change: impl FnOnce(&mut VideoRecord)) -> Result<()> {
+        let mut data = self.lock();
+        change(data.videos.entry(id.to_string()).or_default());
+        self.save(&data)
+    }
+
+    /// Writes through a temp file so a crash never leaves a truncated archive.
+    fn save(&self, data: &ArchiveData) -> Result<()> {
+        let json = serde_json::to_string_pretty(data).context("Failed to serialize archive")?;
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, json)
+            .and_then(|()| fs::rename(&tmp, &self.path))
+            .with_context(|| format!("Failed to write archive {}", self.path.display()))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ArchiveData> {
+        self.data.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_round_trip() {
+        let path = std::env::temp_dir().join(format!("slycer-archive-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let archive = Archive::open(&path).unwrap();
+        archive.start("abc", "https://youtu.be/abc").unwrap();
+        archive
+            .add_tracks("abc", &[PathBuf::from("01.mp3"), PathBuf::from("02.mp3")])
+            .unwrap();
+        assert!(!archive.is_complete_url("https://youtu.be/abc"));
+        archive.complete("abc").unwrap();
+
+        let reopened = Archive::open(&path).unwrap();
+        assert!(reopened.is_complete_url("https://youtu.be/abc"));
+        let record = reopened.get("abc").unwrap();
+        assert_eq!(record.tracks.len(), 2);
+        assert!(record.complete);
+        let _ = fs::remove_file(&path);
+    }
+}
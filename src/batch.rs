@@ -0,0 +1,366 @@
+//! Processing many URLs concurrently with per-host rate limiting.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{
+        Condvar, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::Error;
+use indicatif::{MultiProgress, ProgressBar};
+
+use crate::{
+    interrupt::is_interrupted,
+    job::{JobOptions, download_and_split},
+    progress::{ProgressGroup, add_spinner},
+    split::SplitReport,
+};
+
+/// How a batch of URLs is scheduled.
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    /// URLs processed at the same time
+    pub parallel: usize,
+    /// URLs from one host processed at the same time
+    pub per_host: usize,
+    /// Minimum time between starting two downloads from one host; unused when
+    /// `parallel` is 1
+    pub host_interval: Duration,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            parallel: 1,
+            per_host: 2,
+            host_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+/// One URL of a batch and the options it runs with.
+#[derive(Debug, Clone)]
+pub struct BatchJob {
+    pub url: String,
+    pub opts: JobOptions,
+}
+
+/// Outcome of [`run_batch`], each list in input order.
+#[derive(Debug, Default)]
+pub struct BatchReport {
+    /// URLs processed successfully
+    pub completed: Vec<String>,
+    /// URLs that failed, with their errors
+    pub failed: Vec<(String, Error)>,
+    /// URLs that were running when the batch was interrupted
+    pub interrupted: Vec<String>,
+    /// URLs never started because the batch was interrupted
+    pub not_started: Vec<String>,
+    /// Existing tracks left alone by [`OverwritePolicy::Skip`](crate::split::OverwritePolicy::Skip)
+    pub skipped_tracks: Vec<PathBuf>,
+}
+
+impl BatchReport {
+    /// One-line summary such as `3 of 5 URLs done, 1 failed, 1 not started`,
+    /// followed by the number of skipped tracks if any.
+    #[must_use]
+    pub fn summary(&self) -> String {
+        let total = self.completed.len()
+            + self.failed.len()
+            + self.interrupted.len()
+            + self.not_started.len();
+        let mut summary = format!("{} of {total} URLs done", self.completed.len());
+        for (count, what) in [
+            (self.failed.len(), "failed"),
+            (self.interrupted.len(), "interrupted"),
+            (self.not_started.len(), "not started"),
+        ] {
+            if count > 0 {
+                summary.push_str(&format!(", {count} {what}"));
+            }
+        }
+        if !self.skipped_tracks.is_empty() {
+            summary.push_str(&format!(
+                "; {} existing tracks skipped",
+                self.skipped_tracks.len()
+            ));
+        }
+        summary
+    }
+}
+
+enum Outcome {
+    Completed(SplitReport),
+    Failed(Error),
+    Interrupted,
+}
+
+/// Runs [`download_and_split`] for every job, `batch.parallel` at a time.
+///
+/// Each URL gets its own group of bars in `mp`, headed by the URL. `overall`
+/// is advanced once per finished URL. After an interrupt no new URL starts.
+pub fn run_batch(
+    mp: &MultiProgress,
+    batch: &BatchOptions,
+    jobs: &[BatchJob],
+    overall: &ProgressBar,
+) -> BatchReport {
+    // One URL at a time already keeps a host from being flooded
+    let interval = if batch.parallel > 1 {
+        batch.host_interval
+    } else {
+        Duration::ZERO
+    };
+    let limiter = HostLimiter::new(batch.per_host, interval);
+    let next = AtomicUsize::new(0);
+    let outcomes = Mutex::new(Vec::new());
+    let workers = batch.parallel.max(1).min(jobs.len());
+    let mut outputs = HashMap::new();
+    for output in jobs.iter().filter_map(|job| job.opts.output.as_ref()) {
+        *outputs.entry(output).or_insert(0) += 1;
+    }
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                while !is_interrupted() {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(BatchJob { url, opts }) = jobs.get(index) else {
+                        break;
+                    };
+                    let header = add_spinner(&ProgressGroup::new(mp), url);
+                    header.set_message(format!("{url} (waiting for host)"));
+                    let permit = limiter.acquire(&host_of(url));
+                    header.set_message(url.clone());
+
+                    // One explicit output name can't hold every kept file
+                    let job = JobOptions {
+                        output: match &opts.output {
+                            Some(output) if outputs.get(output) > Some(&1) => {
+                                Some(numbered_output(output, index + 1))
+                            }
+                            other => other.clone(),
+                        },
+                        ..opts.clone()
+                    };
+                    let result = download_and_split(&ProgressGroup::under(mp, &header), &job, url);
+                    drop(permit);
+                    header.finish_and_clear();
+
+                    let outcome = match result {
+                        Ok(split) => Outcome::Completed(split),
+                        Err(_) if is_interrupted() => Outcome::Interrupted,
+                        Err(err) => {
+                            overall.println(format!("\x1b[31m{url}: {err}\x1b[0m"));
+                            Outcome::Failed(err)
+                        }
+                    };
+                    if let Ok(mut outcomes) = outcomes.lock() {
+                        outcomes.push((index, outcome));
+                    }
+                    overall.inc(1);
+                }
+            });
+        }
+    });
+
+    let mut outcomes = outcomes.into_inner().unwrap_or_default();
+    outcomes.sort_by_key(|(index, _)| *index);
+    let mut report = BatchReport::default();
+    let mut outcomes = outcomes.into_iter().peekable();
+    for (index, BatchJob { url, .. }) in jobs.iter().enumerate() {
+        let Some((_, outcome)) = outcomes.next_if(|(i, _)| *i == index) else {
+            report.not_started.push(url.clone());
+            continue;
+        };
+        match outcome {
+            Outcome::Completed(split) => {
+                report.completed.push(url.clone());
+                report.skipped_tracks.extend(split.skipped);
+            }
+            Outcome::Failed(err) => report.failed.push((url.clone(), err)),
+            Outcome::Interrupted => report.interrupted.push(url.clone()),
+        }
+    }
+    report
+}
+
+/// `out.mp3` → `out-3.mp3`, so the jobs of a batch don't share a kept file.
+fn numbered_output(output: &Path, n: usize) -> PathBuf {
+    let stem = output
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let name = match output.extension() {
+        Some(ext) => format!("{stem}-{n}.{}", ext.to_string_lossy()),
+        None => format!("{stem}-{n}"),
+    };
+    output.with_file_name(name)
+}
+
+/// Host part of `url`, lowercased and without `www.`.
+#[must_use]
+pub fn host_of(url: &str) -> String {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = authority.rsplit('@').next().unwrap_or_default();
+    let host = host
+        .split(':')
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    host.strip_prefix("www.")
+        .map_or_else(|| host.clone(), ToOwned::to_owned)
+}
+
+#[derive(Debug, Default)]
+struct HostState {
+    active: usize,
+    last_start: Option<Instant>,
+}
+
+/// Caps concurrent jobs per host and spaces out their starts.
+struct HostLimiter {
+    per_host: usize,
+    interval: Duration,
+    hosts: Mutex<HashMap<String, HostState>>,
+    changed: Condvar,
+}
+
+/// A running job; frees its host slot when dropped.
+struct HostPermit<'a> {
+    limiter: &'a HostLimiter,
+    host: String,
+}
+
+impl HostLimiter {
+    fn new(per_host: usize, interval: Duration) -> Self {
+        Self {
+            per_host: per_host.max(1),
+            interval,
+            hosts: Mutex::new(HashMap::new()),
+            changed: Condvar::new(),
+        }
+    }
+
+    fn acquire(&self, host: &str) -> HostPermit<'_> {
+        let mut hosts = self
+            .hosts
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        loop {
+            let state = hosts.entry(host.to_string()).or_default();
+            let wait = state.last_start.map_or(Duration::ZERO, |t| {
+                self.interval.saturating_sub(t.elapsed())
+            });
+            if state.active < self.per_host && wait.is_zero() {
+                state.active += 1;
+                state.last_start = Some(Instant::now());
+                break;
+            }
+            let timeout = if wait.is_zero() {
+                Duration::from_millis(500)
+            } else {
+                wait
+            };
+            hosts = self
+                .changed
+                .wait_timeout(hosts, timeout)
+                .map_or_else(|e| e.into_inner().0, |(guard, _)| guard);
+        }
+        HostPermit {
+            limiter: self,
+            host: host.to_string(),
+        }
+    }
+}
+
+impl Drop for HostPermit<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut hosts) = self.limiter.hosts.lock()
+            && let Some(state) = hosts.get_mut(&self.host)
+        {
+            state.active = state.active.saturating_sub(1);
+        }
+        self.limiter.changed.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("https://www.youtube.com/watch?v=abc", "youtube.com")]
+    #[case("https://youtu.be/abc", "youtu.be")]
+    #[case("https://user@Music.Example.org:8443/x", "music.example.org")]
+    #[case("youtube.com/watch?v=abc", "youtube.com")]
+    fn test_host_of(#[case] url: &str, #[case] expected: &str) {
+        assert_eq!(host_of(url), expected);
+    }
+
+    #[test]
+    fn test_report_summary() {
+        let report = BatchReport {
+            completed: vec!["a".into(), "b".into()],
+            not_started: vec!["c".into()],
+            ..BatchReport::default()
+        };
+        assert_eq!(report.summary(), "2 of 3 URLs done, 1 not started");
+    }
+
+    #[test]
+    fn test_report_summary_skipped_tracks() {
+        let report = BatchReport {
+            completed: vec!["a".into()],
+            skipped_tracks: vec!["01.mp3".into(), "02.mp3".into()],
+            ..BatchReport::default()
+        };
+        assert_eq!(
+            report.summary(),
+            "1 of 1 URLs done; 2 existing tracks skipped"
+        );
+    }
+
+    #[test]
+    fn test_host_limiter_caps_and_spaces_starts() {
+        let interval = Duration::from_millis(100);
+        let limiter = HostLimiter::new(2, interval);
+        let active = AtomicUsize::new(0);
+        let max_active = AtomicUsize::new(0);
+        let starts = Mutex::new(Vec::new());
+        thread::scope(|scope| {
+            for _ in 0..3 {
+                scope.spawn(|| {
+                    let _permit = limiter.acquire("example.com");
+                    starts.lock().unwrap().push(Instant::now());
+                    let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_active.fetch_max(now, Ordering::SeqCst);
+                    thread::sleep(Duration::from_millis(150));
+                    active.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+        assert_eq!(max_active.load(Ordering::SeqCst), 2);
+        let mut starts = starts.into_inner().unwrap();
+        starts.sort();
+        for pair in starts.windows(2) {
+            // Allow for the time between the limiter's clock and ours
+            assert!(pair[1] - pair[0] >= interval - Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn test_numbered_output() {
+        assert_eq!(
+            numbered_output(Path::new("tmp/out.mp3"), 3),
+            Path::new("tmp/out-3.mp3")
+        );
+    }
+}
@@ -0,0 +1,180 @@
+//! Chapter boundaries and the sources they are read from.
+
+mod file;
+mod timestamps;
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::probe::probe_chapters;
+
+pub use file::{ChapterFormat, load_chapter_file, parse_chapters};
+pub use timestamps::{parse_line, parse_timestamp, parse_timestamp_list};
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Chapter {
+    pub title: String,
+    pub start_time: f64,
+    pub end_time: f64,
+}
+
+/// Where chapter boundaries are taken from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChapterSource {
+    /// The `chapters` array of the `yt-dlp -J` metadata
+    Metadata,
+    /// Timestamp lines (`00:00 Intro`) in the video description
+    Description,
+    /// A text, CUE, ffmetadata or JSON chapter file given by the user
+    File(PathBuf),
+    /// Chapters stored in the media container, read with `ffprobe`
+    Embedded,
+}
+
+/// What chapter sources can look at.
+#[derive(Debug, Clone, Copy)]
+pub struct ChapterContext<'a> {
+    /// `yt-dlp -J` metadata; `Value::Null` for local files
+    pub metadata: &'a Value,
+    /// Media file on disk, probed by [`ChapterSource::Embedded`]
+    pub media: Option<&'a Path>,
+    /// Total length in seconds, closing the last chapter of timestamp lists
+    pub duration: Option<f64>,
+}
+
+impl<'a> ChapterContext<'a> {
+    /// Context for a downloaded video, taking the duration from its metadata.
+    #[must_use]
+    pub fn new(metadata: &'a Value, media: Option<&'a Path>) -> Self {
+        Self {
+            metadata,
+            media,
+            duration: metadata.get("duration").and_then(Value::as_f64),
+        }
+    }
+}
+
+impl ChapterSource {
+    /// Loads chapters from this source.
+    ///
+    /// # Errors
+    /// Fails when the source is missing or malformed.
+    pub fn load(&self, ctx: &ChapterContext<'_>) -> Result<Vec<Chapter>> {
+        match self {
+            Self::Metadata => extract_chapters(ctx.metadata),
+            Self::Description => {
+                let Some(description) = ctx.metadata.get("description").and_then(Value::as_str)
+                else {
+                    bail!("No 'description' field in metadata");
+                };
+                Ok(parse_timestamp_list(description, ctx.duration))
+            }
+            Self::File(path) => load_chapter_file(path, ctx.duration),
+            Self::Embedded => {
+                let Some(media) = ctx.media else {
+                    bail!("No media file to read embedded chapters from");
+                };
+                probe_chapters(media)
+            }
+        }
+    }
+
+    /// Whether a failure of this source must abort the job instead of
+    /// falling through to the next source.
+    #[must_use]
+    pub fn is_explicit(&self) -> bool {
+        matches!(self, Self::File(_))
+    }
+}
+
+/// Options for resolving the chapter list of a video.
+///
+/// The default order is the metadata `chapters` field, then chapters embedded
+/// in the downloaded file, then description timestamps.
+#[derive(Debug, Clone)]
+pub struct ChapterOptions {
+    /// Sources tried in order; the first one yielding chapters wins
+    pub sources: Vec<ChapterSource>,
+}
+
+impl Default for ChapterOptions {
+    fn default() -> Self {
+        Self {
+            sources: vec![
+                ChapterSource::Metadata,
+                ChapterSource::Embedded,
+                ChapterSource::Description,
+            ],
+        }
+    }
+}
+
+/// Returns chapters from the first source in `opts` that yields any.
+///
+/// An empty list means the video should be converted as a whole.
+///
+/// # Errors
+/// Fails when an explicit source (a user-supplied file) cannot be loaded.
+pub fn resolve_chapters(opts: &ChapterOptions, ctx: &ChapterContext<'_>) -> Result<Vec<Chapter>> {
+    for source in &opts.sources {
+        match source.load(ctx) {
+            Ok(chapters) if !chapters.is_empty() => return Ok(chapters),
+            Ok(_) => {}
+            Err(err) if source.is_explicit() => return Err(err),
+            Err(_) => {}
+        }
+    }
+    Ok(Vec::new())
+}
+
+/// Parses the `chapters` field of `yt-dlp -J` metadata.
+///
+/// # Errors
+/// Fails when the field is missing or malformed.
+pub fn extract_chapters(v: &Value) -> Result<Vec<Chapter>> {
+    let Some(chapters_val) = v.get("chapters") else {
+        bail!("No 'chapters' field in metadata");
+    };
+    let chapters: Vec<Chapter> =
+        serde_json::from_value(chapters_val.clone()).context("Failed to parse chapters")?;
+    Ok(chapters)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_resolve_chapters_priority() {
+        let metadata = json!({
+            "duration": 300.0,
+            "description": "00:00 Intro\n02:30 Main",
+        });
+        let ctx = ChapterContext::new(&metadata, None);
+        // No `chapters` field and no media file: falls through to the description
+        let chapters = resolve_chapters(&ChapterOptions::default(), &ctx).unwrap();
+        assert_eq!(chapters.len(), 2);
+        assert_eq!(chapters[1].end_time, 300.0);
+
+        let metadata = json!({
+            "chapters": [{"title": "Only", "start_time": 0.0, "end_time": 300.0}],
+            "description": "00:00 Intro\n02:30 Main",
+        });
+        let ctx = ChapterContext::new(&metadata, None);
+        let chapters = resolve_chapters(&ChapterOptions::default(), &ctx).unwrap();
+        assert_eq!(chapters.len(), 1);
+    }
+
+    #[test]
+    fn test_resolve_chapters_explicit_error() {
+        let opts = ChapterOptions {
+            sources: vec![ChapterSource::File("/nonexistent/chapters.txt".into())],
+        };
+        let metadata = json!({});
+        assert!(resolve_chapters(&opts, &ChapterContext::new(&metadata, None)).is_err());
+    }
+}
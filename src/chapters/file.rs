@@ -0,0 +1,373 @@
+//! Chapter lists loaded from a file: plain `timestamp title` text, CUE sheets,
+//! ffmetadata (`;FFMETADATA1` with `[CHAPTER]` sections) or a JSON array of chapters.
+
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+
+use super::{
+    Chapter,
+    timestamps::{chapters_from_starts, parse_line},
+};
+
+/// Supported chapter file formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChapterFormat {
+    Text,
+    Cue,
+    FfMetadata,
+    Json,
+}
+
+impl ChapterFormat {
+    /// Guesses the format from the extension, then from the content.
+    #[must_use]
+    pub fn detect(path: &Path, content: &str) -> Self {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        match ext.as_deref() {
+            Some("cue") => return Self::Cue,
+            Some("json") => return Self::Json,
+            Some("ffmeta" | "ffmetadata") => return Self::FfMetadata,
+            _ => {}
+        }
+        let trimmed = content.trim_start_matches('\u{feff}').trim_start();
+        if trimmed.starts_with(";FFMETADATA") {
+            Self::FfMetadata
+        } else if is_json_array(trimmed) {
+            Self::Json
+        } else if content
+            .lines()
+            .any(|l| l.trim_start().to_ascii_uppercase().starts_with("INDEX 01"))
+        {
+            Self::Cue
+        } else {
+            Self::Text
+        }
+    }
+}
+
+/// A JSON array, as opposed to text lines starting with `[00:00]`.
+fn is_json_array(content: &str) -> bool {
+    let Some(rest) = content.strip_prefix('[') else {
+        return false;
+    };
+    rest.trim_start().starts_with('{') || serde_json::from_str::<serde_json::Value>(content).is_ok()
+}
+
+/// Reads and parses a chapter file. `duration` closes the last chapter when
+/// the file does not say where it ends.
+///
+/// # Errors
+/// Fails when the file cannot be read, is malformed or holds no chapters.
+pub fn load_chapter_file(path: &Path, duration: Option<f64>) -> Result<Vec<Chapter>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read chapters file {}", path.display()))?;
+    let format = ChapterFormat::detect(path, &content);
+    let chapters = parse_chapters(&content, format, duration)
+        .with_context(|| format!("Invalid chapters file {}", path.display()))?;
+    if chapters.is_empty() {
+        bail!("No chapters found in {}", path.display());
+    }
+    Ok(chapters)
+}
+
+/// Parses chapter file `content` written in `format`.
+///
+/// # Errors
+/// Fails on malformed content.
+pub fn parse_chapters(
+    content: &str,
+    format: ChapterFormat,
+    duration: Option<f64>,
+) -> Result<Vec<Chapter>> {
+    let content = content.trim_start_matches('\u{feff}');
+    let entries = match format {
+        ChapterFormat::Text => parse_text(content)?,
+        ChapterFormat::Cue => parse_cue(content)?,
+        ChapterFormat::FfMetadata => return parse_ffmetadata(content),
+        ChapterFormat::Json => return parse_json(content, duration),
+    };
+    Ok(chapters_from_starts(entries, duration))
+}
+
+fn parse_text(content: &str) -> Result<Vec<(f64, String)>> {
+    let mut entries: Vec<(f64, String)> = Vec::new();
+    for (lineno, line) in content.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || is_comment(trimmed) {
+            continue;
+        }
+        let Some((start, title)) = parse_line(trimmed) else {
+            bail!("line {}: no timestamp in '{trimmed}'", lineno + 1);
+        };
+        ensure_ascending(&entries, start, lineno + 1)?;
+        entries.push((start, title));
+    }
+    Ok(entries)
+}
+
+/// `#` starts a comment, except in `#3 0:00 Song` where it numbers a track.
+fn is_comment(line: &str) -> bool {
+    let Some(rest) = line.strip_prefix('#') else {
+        return false;
+    };
+    let numbered = rest.starts_with(|c: char| c.is_ascii_digit());
+    !numbered || parse_line(line).is_none()
+}
+
+fn parse_cue(content: &str) -> Result<Vec<(f64, String)>> {
+    struct Track {
+        title: Option<String>,
+        performer: Option<String>,
+        start: Option<f64>,
+    }
+
+    let mut tracks: Vec<Track> = Vec::new();
+    for (lineno, line) in content.lines().enumerate() {
+        let line = line.trim();
+        let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+        match command.to_ascii_uppercase().as_str() {
+            "TRACK" => tracks.push(Track {
+                title: None,
+                performer: None,
+                start: None,
+            }),
+            // Album-level TITLE/PERFORMER come before the first TRACK and are ignored
+            "TITLE" => {
+                if let Some(track) = tracks.last_mut() {
+                    track.title = Some(unquote(rest));
+                }
+            }
+            "PERFORMER" => {
+                if let Some(track) = tracks.last_mut() {
+                    track.performer = Some(unquote(rest));
+                }
+            }
+            "INDEX" => {
+                let mut parts = rest.split_whitespace();
+                if parts.next() != Some("01") {
+                    continue;
+                }
+                let Some(start) = parts.next().and_then(parse_cue_time) else {
+                    bail!("line {}: invalid INDEX time in '{line}'", lineno + 1);
+                };
+                if let Some(track) = tracks.last_mut() {
+                    track.start = Some(start);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut entries: Vec<(f64, String)> = Vec::new();
+    for (i, track) in tracks.into_iter().enumerate() {
+        let Some(start) = track.start else {
+            bail!("track {} has no INDEX 01", i + 1);
+        };
+        ensure_ascending(&entries, start, i + 1)?;
+        let title = match (track.performer, track.title) {
+            (Some(performer), Some(title)) => format!("{performer} - {title}"),
+            (None, Some(title)) => title,
+            (Some(performer), None) => performer,
+            (None, None) => String::new(),
+        };
+        entries.push((start, title));
+    }
+    Ok(entries)
+}
+
+/// Parses a CUE `MM:SS:FF` time (75 frames per second).
+fn parse_cue_time(value: &str) -> Option<f64> {
+    let mut parts = value.split(':').map(|p| p.parse::<u32>().ok());
+    let (minutes, seconds, frames) = (parts.next()??, parts.next()??, parts.next()??);
+    if parts.next().is_some() || seconds >= 60 || frames >= 75 {
+        return None;
+    }
+    Some(f64::from(minutes) * 60.0 + f64::from(seconds) + f64::from(frames) / 75.0)
+}
+
+fn unquote(value: &str) -> String {
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value)
+        .to_string()
+}
+
+fn parse_ffmetadata(content: &str) -> Result<Vec<Chapter>> {
+    #[derive(Default)]
+    struct Section {
+        timebase: Option<(f64, f64)>,
+        start: Option<f64>,
+        end: Option<f64>,
+        title: Option<String>,
+    }
+
+    let mut sections: Vec<Section> = Vec::new();
+    let mut in_chapter = false;
+    for (lineno, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('[') {
+            in_chapter = line.eq_ignore_ascii_case("[CHAPTER]");
+            if in_chapter {
+                sections.push(Section::default());
+            }
+            continue;
+        }
+        let Some(section) = sections.last_mut().filter(|_| in_chapter) else {
+            continue;
+        };
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let invalid = || format!("line {}: invalid value in '{line}'", lineno + 1);
+        match key.to_ascii_uppercase().as_str() {
+            "TIMEBASE" => {
+                let (num, den) = value.split_once('/').with_context(invalid)?;
+                let num: f64 = num.trim().parse().with_context(invalid)?;
+                let den: f64 = den.trim().parse().with_context(invalid)?;
+                if den == 0.0 {
+                    bail!(invalid());
+                }
+                section.timebase = Some((num, den));
+            }
+            "START" => section.start = Some(value.trim().parse().with_context(invalid)?),
+            "END" => section.end = Some(value.trim().parse().with_context(invalid)?),
+            "TITLE" => section.title = Some(unescape_ffmetadata(value)),
+            _ => {}
+        }
+    }
+
+    sections
+        .into_iter()
+        .enumerate()
+        .map(|(i, section)| {
+            let (num, den) = section.timebase.unwrap_or((1.0, 1_000_000_000.0));
+            let (Some(start), Some(end)) = (section.start, section.end) else {
+                bail!("chapter {} is missing START or END", i + 1);
+            };
+            Ok(Chapter {
+                title: section.title.unwrap_or_else(|| format!("Track {}", i + 1)),
+                start_time: start * num / den,
+                end_time: end * num / den,
+            })
+        })
+        .collect()
+}
+
+fn unescape_ffmetadata(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '\\' {
+            if let Some(next) = chars.next() {
+                out.push(next);
+            }
+        } else {
+            out.push(ch);
+        }
+    }
+    out
+}
+
+fn parse_json(content: &str, duration: Option<f64>) -> Result<Vec<Chapter>> {
+    #[derive(Deserialize)]
+    struct JsonChapter {
+        title: String,
+        start_time: f64,
+        end_time: Option<f64>,
+    }
+
+    let items: Vec<JsonChapter> = serde_json::from_str(content).context("Invalid JSON")?;
+    if items.iter().all(|c| c.end_time.is_some()) {
+        return Ok(items
+            .into_iter()
+            .map(|c| Chapter {
+                title: c.title,
+                start_time: c.start_time,
+                end_time: c.end_time.unwrap_or(f64::INFINITY),
+            })
+            .collect());
+    }
+    let mut entries: Vec<(f64, String)> = Vec::new();
+    for (i, item) in items.into_iter().enumerate() {
+        ensure_ascending(&entries, item.start_time, i + 1)?;
+        entries.push((item.start_time, item.title));
+    }
+    Ok(chapters_from_starts(entries, duration))
+}
+
+fn ensure_ascending(entries: &[(f64, String)], start: f64, position: usize) -> Result<()> {
+    if let Some((prev, _)) = entries.last()
+        && start <= *prev
+    {
+        bail!("entry {position}: start {start}s is not after the previous one ({prev}s)");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn summary(chapters: &[Chapter]) -> Vec<(String, f64, f64)> {
+        chapters
+            .iter()
+            .map(|c| (c.title.clone(), c.start_time, c.end_time))
+            .collect()
+    }
+
+    #[rstest]
+    #[case(ChapterFormat::Text, "# comment\n0:00 Intro\n\n2:30 Main\n")]
+    #[case(ChapterFormat::Text, "[00:00] Intro\n[02:30] Main\n")]
+    #[case(ChapterFormat::Text, "# tracklist\n#1 0:00 Intro\n#2 2:30 Main\n")]
+    #[case(
+        ChapterFormat::Cue,
+        "PERFORMER \"Someone\"\nTITLE \"Album\"\nFILE \"mix.flac\" WAVE\n  TRACK 01 AUDIO\n    TITLE \"Intro\"\n    INDEX 01 00:00:00\n  TRACK 02 AUDIO\n    TITLE \"Main\"\n    INDEX 00 02:29:00\n    INDEX 01 02:30:00\n"
+    )]
+    #[case(
+        ChapterFormat::FfMetadata,
+        ";FFMETADATA1\ntitle=Album\n[CHAPTER]\nTIMEBASE=1/1000\nSTART=0\nEND=150000\ntitle=Intro\n[CHAPTER]\nTIMEBASE=1/1000\nSTART=150000\nEND=300000\ntitle=Main\n"
+    )]
+    #[case(
+        ChapterFormat::Json,
+        r#"[{"title": "Intro", "start_time": 0}, {"title": "Main", "start_time": 150}]"#
+    )]
+    fn test_parse_chapters(#[case] format: ChapterFormat, #[case] content: &str) {
+        let chapters = parse_chapters(content, format, Some(300.0)).unwrap();
+        assert_eq!(
+            summary(&chapters),
+            vec![
+                ("Intro".to_string(), 0.0, 150.0),
+                ("Main".to_string(), 150.0, 300.0)
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case("tracks.cue", "", ChapterFormat::Cue)]
+    #[case("tracks.txt", ";FFMETADATA1\n", ChapterFormat::FfMetadata)]
+    #[case("tracks.txt", "[{\"title\": \"a\"}]", ChapterFormat::Json)]
+    #[case("tracks", "TRACK 01 AUDIO\nINDEX 01 00:00:00", ChapterFormat::Cue)]
+    #[case("tracks.txt", "00:00 Intro", ChapterFormat::Text)]
+    #[case("tracks.txt", "[00:00] Intro\n[02:30] Main", ChapterFormat::Text)]
+    #[case("tracks.txt", "[\n  {\"title\": \"a\"}\n]", ChapterFormat::Json)]
+    #[case("tracks.txt", "[]", ChapterFormat::Json)]
+    fn test_detect(#[case] path: &str, #[case] content: &str, #[case] expected: ChapterFormat) {
+        assert_eq!(ChapterFormat::detect(Path::new(path), content), expected);
+    }
+
+    #[test]
+    fn test_parse_text_rejects_unordered() {
+        assert!(parse_chapters("2:00 B\n1:00 A\n", ChapterFormat::Text, None).is_err());
+    }
+}
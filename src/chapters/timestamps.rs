@@ -0,0 +1,188 @@
+//! Tracklists written as `timestamp title` lines, as found in video descriptions.
+//!
+//! Accepted line shapes include `00:00 Intro`, `1:02:15 - Outro`, `3. [04:10] Song`,
+//! `0:00 - 3:12 Song` (ranges) and `Song – 04:10` (timestamp at the end).
+
+use super::Chapter;
+
+/// Separators allowed between a timestamp and the title.
+const SEPARATORS: &[char] = &['-', '–', '—', '|', ':', '•', '·', '~'];
+
+/// Parses every line carrying a timestamp into chapters.
+///
+/// Lines whose timestamp is not later than the previous one are ignored, so
+/// stray mentions (`at 2:30 the drop`) rarely break a list. End times come
+/// from the next start; the last chapter ends at `duration` or, when unknown,
+/// at the end of the file (`f64::INFINITY`). Fewer than two entries yield no
+/// chapters.
+#[must_use]
+pub fn parse_timestamp_list(text: &str, duration: Option<f64>) -> Vec<Chapter> {
+    let mut entries: Vec<(f64, String)> = Vec::new();
+    for line in text.lines() {
+        let Some((start, title)) = parse_line(line) else {
+            continue;
+        };
+        if entries.last().is_some_and(|(prev, _)| start <= *prev) {
+            continue;
+        }
+        entries.push((start, title));
+    }
+    if entries.len() < 2 {
+        return Vec::new();
+    }
+    chapters_from_starts(entries, duration)
+}
+
+/// Builds chapters from ascending `(start, title)` pairs.
+pub(crate) fn chapters_from_starts(
+    entries: Vec<(f64, String)>,
+    duration: Option<f64>,
+) -> Vec<Chapter> {
+    let starts: Vec<f64> = entries.iter().map(|(start, _)| *start).collect();
+    entries
+        .into_iter()
+        .enumerate()
+        .map(|(i, (start_time, title))| {
+            let end_time = starts
+                .get(i + 1)
+                .copied()
+                .or(duration.filter(|d| *d > start_time))
+                .unwrap_or(f64::INFINITY);
+            let title = if title.is_empty() {
+                format!("Track {}", i + 1)
+            } else {
+                title
+            };
+            Chapter {
+                title,
+                start_time,
+                end_time,
+            }
+        })
+        .collect()
+}
+
+/// Parses one line into its start time and title.
+#[must_use]
+pub fn parse_line(line: &str) -> Option<(f64, String)> {
+    let mut tokens: Vec<&str> = line.split_whitespace().collect();
+    if tokens.is_empty() {
+        return None;
+    }
+
+    // Leading track number: `1.`, `01)`, `#3`, or a bare `01` followed by a timestamp
+    if tokens.len() > 1 && is_track_number(tokens[0]) && parse_timestamp(tokens[1]).is_some() {
+        tokens.remove(0);
+    }
+
+    if let Some(start) = parse_timestamp(tokens[0]) {
+        let mut rest = &tokens[1..];
+        // Ranges: `0:00 - 3:12 Title` or `0:00 3:12 Title`
+        if let Some(pos) = rest.iter().position(|t| !is_separator(t))
+            && parse_timestamp(rest[pos]).is_some()
+        {
+            rest = &rest[pos + 1..];
+        }
+        return Some((start, clean_title(&rest.join(" "))));
+    }
+
+    let last = tokens.len() - 1;
+    if last > 0
+        && let Some(start) = parse_timestamp(tokens[last])
+    {
+        return Some((start, clean_title(&tokens[..last].join(" "))));
+    }
+    None
+}
+
+/// Parses `M:SS`, `MM:SS`, `MMM:SS` or `H:MM:SS`, optionally wrapped in
+/// brackets or followed by a separator.
+#[must_use]
+pub fn parse_timestamp(token: &str) -> Option<f64> {
+    let token = token
+        .trim_start_matches(['[', '(', '{'])
+        .trim_end_matches(|c: char| SEPARATORS.contains(&c) || matches!(c, ']' | ')' | '}' | ','));
+    let parts: Vec<&str> = token.split(':').collect();
+    let (first, rest) = parts.split_first()?;
+    let is_number = |p: &str, min: usize, max: usize| {
+        (min..=max).contains(&p.len()) && p.chars().all(|c| c.is_ascii_digit())
+    };
+    if !is_number(first, 1, 3)
+        || !(1..=2).contains(&rest.len())
+        || !rest.iter().all(|p| is_number(p, 2, 2))
+    {
+        return None;
+    }
+    let nums: Vec<u64> = parts
+        .iter()
+        .map(|p| p.parse().ok())
+        .collect::<Option<_>>()?;
+    let total = match nums.as_slice() {
+        [m, s] if *s < 60 => m * 60 + s,
+        [h, m, s] if *m < 60 && *s < 60 => h * 3600 + m * 60 + s,
+        _ => return None,
+    };
+    #[allow(clippy::cast_precision_loss)]
+    Some(total as f64)
+}
+
+fn is_track_number(token: &str) -> bool {
+    let digits = token.trim_start_matches('#').trim_end_matches(['.', ')']);
+    !digits.is_empty() && digits.len() <= 3 && digits.chars().all(|c| c.is_ascii_digit())
+}
+
+fn is_separator(token: &str) -> bool {
+    token.chars().all(|c| SEPARATORS.contains(&c))
+}
+
+fn clean_title(title: &str) -> String {
+    title
+        .trim_matches(|c: char| c.is_whitespace() || SEPARATORS.contains(&c))
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("00:00 Intro", Some((0.0, "Intro")))]
+    #[case("1:02:15 Outro", Some((3735.0, "Outro")))]
+    #[case("12. 4:10 – Song Name", Some((250.0, "Song Name")))]
+    #[case("03) [04:10] Song Name", Some((250.0, "Song Name")))]
+    #[case("0:00 - 3:12 Range Song", Some((0.0, "Range Song")))]
+    #[case("Artist - Song – 10:05", Some((605.0, "Artist - Song")))]
+    #[case("Song (1:00:00)", Some((3600.0, "Song")))]
+    #[case("Follow me on twitter", None)]
+    #[case("3:7 not a timestamp", None)]
+    #[case("1:75 not a timestamp", None)]
+    fn test_parse_line(#[case] line: &str, #[case] expected: Option<(f64, &str)>) {
+        assert_eq!(
+            parse_line(line),
+            expected.map(|(start, title)| (start, title.to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_timestamp_list() {
+        let description = "My mix!\n\nTracklist:\n00:00 Intro\n1. 02:30 - First\nat 1:00 the drop\n2. 05:00 - Second\n";
+        let chapters = parse_timestamp_list(description, Some(400.0));
+        let expected = [
+            ("Intro", 0.0, 150.0),
+            ("First", 150.0, 300.0),
+            ("Second", 300.0, 400.0),
+        ];
+        assert_eq!(chapters.len(), expected.len());
+        for (chapter, (title, start, end)) in chapters.iter().zip(expected) {
+            assert_eq!(chapter.title, title);
+            assert!((chapter.start_time - start).abs() < f64::EPSILON);
+            assert!((chapter.end_time - end).abs() < f64::EPSILON);
+        }
+    }
+
+    #[test]
+    fn test_parse_timestamp_list_single_entry() {
+        assert!(parse_timestamp_list("Best part at 2:30", Some(400.0)).is_empty());
+    }
+}
@@ -0,0 +1,134 @@
+//! Cover art taken from the video thumbnail.
+
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{Context, Result};
+
+use crate::{metadata::VideoInfo, process::run_command};
+
+/// Extensions whose containers can carry a front cover (ID3 APIC, MP4 covr, FLAC PICTURE).
+pub const COVER_FORMATS: &[&str] = &["mp3", "m4a", "mp4", "flac"];
+
+/// URL of the largest thumbnail, falling back to the `thumbnail` field.
+#[must_use]
+pub fn best_thumbnail(info: &VideoInfo) -> Option<&str> {
+    info.thumbnails
+        .iter()
+        .filter(|t| !t.url.is_empty())
+        .max_by_key(|t| {
+            (
+                t.preference.unwrap_or(0),
+                t.width.unwrap_or(0).saturating_mul(t.height.unwrap_or(0)),
+            )
+        })
+        .map(|t| t.url.as_str())
+        .or(info.thumbnail.as_deref())
+}
+
+/// Downloads the thumbnail at `url` and stores it as JPEG at `out`,
+/// center-cropped to a square when `square` is set.
+///
+/// # Errors
+/// Fails when `ffmpeg` cannot fetch or convert the image.
+pub fn fetch_cover(url: &str, out: &Path, square: bool) -> Result<()> {
+    let mut cmd = Command::new("ffmpeg");
+    cmd.args(["-hide_banner", "-loglevel", "error", "-y", "-i", url]);
+    if square {
+        cmd.args(["-vf", "crop='min(iw,ih)':'min(iw,ih)'"]);
+    }
+    cmd.args(["-frames:v", "1", "-update", "1", "-pix_fmt", "yuvj420p"])
+        .arg(out);
+    run_command(&mut cmd).context("ffmpeg failed to fetch the thumbnail")
+}
+
+/// Path of the cover image stored next to the combined audio file.
+#[must_use]
+pub fn cover_path(output: &Path) -> PathBuf {
+    output.with_extension("cover.jpg")
+}
+
+/// Whether `path`'s container can hold a cover.
+#[must_use]
+pub fn supports_cover(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|ext| COVER_FORMATS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// `ffmpeg` input arguments adding `cover` as the second input.
+pub(crate) fn cover_input_args(cover: &Path) -> Vec<String> {
+    vec!["-i".to_string(), cover.to_string_lossy().into_owned()]
+}
+
+/// `ffmpeg` output arguments for `out_path` in a command with the cover as
+/// second input: attach it as front cover, or map only the audio when the
+/// container can't hold one (ffmpeg would otherwise pick the image as video).
+pub(crate) fn cover_output_args(out_path: &Path) -> Vec<String> {
+    let args: &[&str] = if supports_cover(out_path) {
+        &[
+            "-map",
+            "0:a",
+            "-map",
+            "1:v",
+            "-c:v",
+            "copy",
+            "-disposition:v",
+            "attached_pic",
+            "-metadata:s:v",
+            "title=Album cover",
+            "-metadata:s:v",
+            "comment=Cover (front)",
+        ]
+    } else {
+        &["-map", "0:a"]
+    };
+    args.iter().map(ToString::to_string).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::Thumbnail;
+
+    fn thumb(url: &str, width: u64, height: u64, preference: i64) -> Thumbnail {
+        Thumbnail {
+            url: url.into(),
+            width: Some(width),
+            height: Some(height),
+            preference: Some(preference),
+        }
+    }
+
+    #[test]
+    fn test_best_thumbnail() {
+        let info = VideoInfo {
+            thumbnail: Some("fallback".into()),
+            thumbnails: vec![
+                thumb("small", 120, 90, 0),
+                thumb("large", 1280, 720, 0),
+                thumb("preferred", 640, 480, 1),
+            ],
+            ..VideoInfo::default()
+        };
+        assert_eq!(best_thumbnail(&info), Some("preferred"));
+        assert_eq!(
+            best_thumbnail(&VideoInfo {
+                thumbnail: Some("fallback".into()),
+                ..VideoInfo::default()
+            }),
+            Some("fallback")
+        );
+    }
+
+    #[test]
+    fn test_cover_output_args() {
+        let mp3 = cover_output_args(Path::new("a.mp3"));
+        assert!(mp3.windows(2).any(|w| w == ["-map", "1:v"]));
+        assert_eq!(cover_output_args(Path::new("a.opus")), ["-map", "0:a"]);
+        assert_eq!(cover_output_args(Path::new("a.wav")), ["-map", "0:a"]);
+        assert_eq!(cover_output_args(Path::new("a.aac")), ["-map", "0:a"]);
+    }
+}
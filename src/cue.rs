@@ -0,0 +1,138 @@
+//! CUE sheets describing the combined audio file.
+
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+
+use crate::{chapters::Chapter, metadata::VideoInfo, tags::TrackTags};
+
+/// Renders a CUE sheet for `audio_file` (a name relative to the sheet).
+///
+/// Without chapters the sheet holds a single track covering the whole file.
+#[must_use]
+pub fn render_cue(audio_file: &str, info: &VideoInfo, chapters: &[Chapter]) -> String {
+    let mut out = String::new();
+    if let Some(date) = &info.upload_date {
+        out.push_str(&format!("REM DATE {}\n", date.get(..4).unwrap_or(date)));
+    }
+    if let Some(url) = &info.webpage_url {
+        out.push_str(&format!("REM COMMENT {}\n", quote(url)));
+    }
+    if let Some(performer) = info.artist.as_ref().or(info.uploader.as_ref()) {
+        out.push_str(&format!("PERFORMER {}\n", quote(performer)));
+    }
+    if let Some(title) = &info.title {
+        out.push_str(&format!("TITLE {}\n", quote(title)));
+    }
+    out.push_str(&format!(
+        "FILE {} {}\n",
+        quote(audio_file),
+        file_type(audio_file)
+    ));
+
+    let whole = [Chapter {
+        title: info.title.clone().unwrap_or_default(),
+        start_time: 0.0,
+        end_time: f64::INFINITY,
+    }];
+    let tracks = if chapters.is_empty() {
+        &whole[..]
+    } else {
+        chapters
+    };
+    for (index, ch) in tracks.iter().enumerate() {
+        let tags = TrackTags::for_chapter(info, index, tracks.len(), &ch.title);
+        out.push_str(&format!("  TRACK {:02} AUDIO\n", index + 1));
+        if let Some(title) = tags.title.filter(|t| !t.is_empty()) {
+            out.push_str(&format!("    TITLE {}\n", quote(&title)));
+        }
+        if let Some(artist) = tags.artist {
+            out.push_str(&format!("    PERFORMER {}\n", quote(&artist)));
+        }
+        out.push_str(&format!("    INDEX 01 {}\n", cue_time(ch.start_time)));
+    }
+    out
+}
+
+/// Writes the CUE sheet for `audio` to `cue_path`, referencing the audio by file name.
+///
+/// # Errors
+/// Fails when the sheet cannot be written.
+pub fn write_cue(
+    cue_path: &Path,
+    audio: &Path,
+    info: &VideoInfo,
+    chapters: &[Chapter],
+) -> Result<()> {
+    let audio_file = audio
+        .file_name()
+        .map_or_else(|| audio.to_string_lossy(), |n| n.to_string_lossy());
+    fs::write(cue_path, render_cue(&audio_file, info, chapters))
+        .with_context(|| format!("Failed to write {}", cue_path.display()))
+}
+
+/// Formats seconds as CUE `MM:SS:FF` (75 frames per second).
+fn cue_time(seconds: f64) -> String {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let frames = (seconds.max(0.0) * 75.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}",
+        frames / (75 * 60),
+        frames / 75 % 60,
+        frames % 75
+    )
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "'"))
+}
+
+fn file_type(audio_file: &str) -> &'static str {
+    let ext = Path::new(audio_file)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase);
+    match ext.as_deref() {
+        Some("mp3") => "MP3",
+        Some("aiff" | "aif") => "AIFF",
+        _ => "WAVE",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chapters::{ChapterFormat, parse_chapters};
+
+    #[test]
+    fn test_render_cue_round_trip() {
+        let info = VideoInfo {
+            title: Some("Live \"Set\"".into()),
+            uploader: Some("DJ Someone".into()),
+            upload_date: Some("20240615".into()),
+            ..VideoInfo::default()
+        };
+        let chapters = vec![
+            Chapter {
+                title: "Intro".into(),
+                start_time: 0.0,
+                end_time: 61.5,
+            },
+            Chapter {
+                title: "Artist - Song".into(),
+                start_time: 61.5,
+                end_time: 3700.0,
+            },
+        ];
+        let cue = render_cue("set.flac", &info, &chapters);
+        assert!(cue.contains("TITLE \"Live 'Set'\"\nFILE \"set.flac\" WAVE\n"));
+        assert!(
+            cue.contains("    TITLE \"Song\"\n    PERFORMER \"Artist\"\n    INDEX 01 01:01:38\n")
+        );
+
+        let parsed = parse_chapters(&cue, ChapterFormat::Cue, Some(3700.0)).unwrap();
+        assert_eq!(parsed[0].title, "DJ Someone - Intro");
+        assert_eq!(parsed[1].title, "Artist - Song");
+        assert!((parsed[1].start_time - 61.5).abs() < 0.02);
+    }
+}
@@ -0,0 +1,208 @@
+//! Detection and auto-installation of the external tools slycer drives.
+
+use std::{
+    io::{self, Write},
+    process::Command,
+};
+
+use anyhow::{Context, Result, bail};
+use which::which;
+
+use crate::{process::run_streaming_lines, progress::new_spinner};
+
+/// Binaries needed to download and split.
+pub const DOWNLOAD_BINARIES: &[&str] = &["yt-dlp", "ffmpeg"];
+/// Binaries needed to split local files (`ffprobe` ships with `ffmpeg`).
+pub const SPLIT_BINARIES: &[&str] = &["ffmpeg"];
+
+/// Checks that `required` binaries are on `PATH`, offering to install the missing ones.
+///
+/// With `auto_yes` the installation runs without asking.
+///
+/// # Errors
+/// Fails when the user declines or installation does not succeed.
+pub fn ensure_binaries_present(required: &[&str], auto_yes: bool) -> Result<()> {
+    let missing: Vec<&str> = required
+        .iter()
+        .copied()
+        .filter(|bin| which(bin).is_err())
+        .collect();
+
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    let msg_list = missing.join(", ");
+    if !auto_yes {
+        eprint!("Missing binaries: {msg_list}. Install automatically? [y/N]: ");
+        io::stderr().flush().ok();
+
+        let mut answer = String::new();
+        io::stdin()
+            .read_line(&mut answer)
+            .context("Failed to read user input")?;
+        let ans = answer.trim().to_ascii_lowercase();
+        let yes = matches!(ans.as_str(), "y" | "yes" | "д" | "да");
+        if !yes {
+            bail!("Required: {msg_list}. Install manually or run with --yes for auto-install");
+        }
+    }
+
+    install_missing(&missing)?;
+
+    // Re-check
+    for bin in &missing {
+        which(bin).with_context(|| format!("Бинарник '{bin}' не найден после установки"))?;
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_lines)]
+fn install_missing(missing: &[&str]) -> Result<()> {
+    let spinner = new_spinner("Installing dependencies");
+
+    #[allow(clippy::match_same_arms)]
+    let installer = match std::env::consts::OS {
+        "macos" => choose_first_available(&["brew"]),
+        "linux" => choose_first_available(&["apt-get", "dnf", "yum", "pacman", "zypper", "apk"]),
+        "windows" => choose_first_available(&["winget", "choco", "scoop"]),
+        _ => None,
+    };
+
+    let Some(installer) = installer else {
+        spinner.finish_with_message("Auto-install is unavailable");
+        bail!(
+            "Cannot determine package manager. Install manually: {}",
+            missing.join(", ")
+        );
+    };
+
+    // Run installers with minimal dependency footprint where possible
+    let result: Result<()> = if cfg!(target_os = "macos") && installer == "brew" {
+        // Disable auto-update and cleanup, install only requested formulae
+        let mut cmd = Command::new("brew");
+        cmd.env("HOMEBREW_NO_AUTO_UPDATE", "1")
+            .env("HOMEBREW_NO_INSTALL_CLEANUP", "1")
+            .env("HOMEBREW_NO_ANALYTICS", "1")
+            .args(["install", "--formula"])
+            .args(missing);
+        run_streaming_lines(&spinner, &mut cmd)
+    } else if cfg!(target_os = "linux") {
+        match installer {
+            "apt-get" => {
+                // Update index only, then install without recommends and without upgrading existing pkgs
+                run_streaming_lines(
+                    &spinner,
+                    Command::new("sudo").args(["-n", "apt-get", "update"]),
+                )?;
+                run_streaming_lines(
+                    &spinner,
+                    Command::new("sudo")
+                        .args([
+                            "-n",
+                            "apt-get",
+                            "install",
+                            "-y",
+                            "--no-install-recommends",
+                            "--no-upgrade",
+                        ])
+                        .args(missing),
+                )
+            }
+            "dnf" => run_streaming_lines(
+                &spinner,
+                Command::new("sudo")
+                    .args([
+                        "-n",
+                        "dnf",
+                        "install",
+                        "-y",
+                        "--setopt=install_weak_deps=False",
+                    ])
+                    .args(missing),
+            ),
+            "yum" => run_streaming_lines(
+                &spinner,
+                Command::new("sudo")
+                    .args(["-n", "yum", "install", "-y"])
+                    .args(missing),
+            ),
+            "pacman" => run_streaming_lines(
+                &spinner,
+                Command::new("sudo")
+                    .args(["-n", "pacman", "-S", "--noconfirm", "--needed"])
+                    .args(missing),
+            ),
+            "zypper" => run_streaming_lines(
+                &spinner,
+                Command::new("sudo")
+                    .args(["-n", "zypper", "install", "-y", "--no-recommends"])
+                    .args(missing),
+            ),
+            "apk" => run_streaming_lines(
+                &spinner,
+                Command::new("sudo")
+                    .args(["-n", "apk", "add", "--no-cache"])
+                    .args(missing),
+            ),
+            _ => Err(anyhow::anyhow!("unsupported installer")),
+        }
+    } else if cfg!(target_os = "windows") {
+        match installer {
+            // Map to more exact IDs when possible
+            "winget" => {
+                let mapped: Vec<String> = missing
+                    .iter()
+                    .map(|&p| match p {
+                        "ffmpeg" => "Gyan.FFmpeg".to_string(),
+                        "yt-dlp" => "yt-dlp.yt-dlp".to_string(),
+                        other => other.to_string(),
+                    })
+                    .collect();
+                run_streaming_lines(
+                    &spinner,
+                    Command::new("winget")
+                        .args([
+                            "install",
+                            "--silent",
+                            "--accept-package-agreements",
+                            "--accept-source-agreements",
+                            "--exact",
+                        ])
+                        .args(&mapped),
+                )
+            }
+            "choco" => run_streaming_lines(
+                &spinner,
+                Command::new("choco")
+                    .args(["install", "-y", "--no-progress"])
+                    .args(missing),
+            ),
+            "scoop" => run_streaming_lines(
+                &spinner,
+                Command::new("scoop").args(["install"]).args(missing),
+            ),
+            _ => Err(anyhow::anyhow!("unsupported installer")),
+        }
+    } else {
+        Err(anyhow::anyhow!("unsupported os"))
+    };
+
+    if result.is_ok() {
+        spinner.finish_with_message("Dependencies installed");
+        Ok(())
+    } else {
+        spinner.finish_with_message("Auto-install failed");
+        bail!(
+            "Failed to install: {}. Install manually via package manager",
+            missing.join(", ")
+        );
+    }
+}
+
+fn choose_first_available<'a>(candidates: &'a [&'a str]) -> Option<&'a str> {
+    candidates
+        .iter()
+        .find(|&&name| which(name).is_ok())
+        .copied()
+}
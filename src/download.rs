@@ -0,0 +1,320 @@
+//! Audio download and metadata retrieval via `yt-dlp`.
+
+use std::{
+    collections::VecDeque,
+    fs,
+    io::{BufRead, BufReader},
+    path::PathBuf,
+    process::{Command, Stdio},
+    sync::{Arc, Mutex, PoisonError},
+    thread,
+    time::Duration,
+};
+
+use anyhow::{Context, Result, bail};
+use indicatif::{ProgressBar, ProgressStyle};
+use serde_json::Value;
+
+use crate::{
+    process::{run_output, spawn, wait},
+    progress::{ProgressGroup, add_spinner},
+};
+
+/// Options for fetching a single video's audio.
+#[derive(Debug, Clone)]
+pub struct DownloadOptions {
+    /// Audio format for yt-dlp extraction
+    pub audio_format: String,
+    /// Output audio file path; with `native` only its stem is used and the
+    /// extension follows the downloaded stream
+    pub output: PathBuf,
+    /// Keep the best audio stream as served (opus/webm, m4a) instead of
+    /// transcoding it to `audio_format`
+    pub native: bool,
+}
+
+/// Downloads combined audio and metadata for a URL.
+#[derive(Debug, Clone)]
+pub struct Downloader {
+    opts: DownloadOptions,
+}
+
+impl Downloader {
+    #[must_use]
+    pub fn new(opts: DownloadOptions) -> Self {
+        Self { opts }
+    }
+
+    #[must_use]
+    pub fn options(&self) -> &DownloadOptions {
+        &self.opts
+    }
+
+    /// Downloads the audio of `url` into `output`, showing progress in `mp`.
+    ///
+    /// Returns the path of the downloaded file, which differs from `output` in
+    /// its extension for native downloads.
+    ///
+    /// # Errors
+    /// Fails when `yt-dlp` cannot be started or exits unsuccessfully.
+    pub fn download(&self, mp: &ProgressGroup, url: &str) -> Result<PathBuf> {
+        // Download progress bar (starts as bar; will remain bar even if no percent)
+        let dl_bar = mp.add(ProgressBar::new(1000));
+        if let Ok(style) = ProgressStyle::with_template(
+            "{spinner:.green} \x1b[90m{elapsed_precise}\x1b[0m [{bar:40.cyan/blue}] {msg}",
+        ) {
+            dl_bar.set_style(style.progress_chars("#>-"));
+        }
+        dl_bar.set_message("Downloading audio");
+        dl_bar.set_position(0);
+        dl_bar.enable_steady_tick(Duration::from_millis(100));
+
+        // Logs bars: show last 5 non-progress lines (each on its own line, gray, no spinners)
+        let mut logs_bars: Vec<ProgressBar> = Vec::with_capacity(5);
+        for _ in 0..5 {
+            let bar = mp.add(ProgressBar::new(0));
+            if let Ok(style) = ProgressStyle::with_template("\x1b[90m{msg}\x1b[0m") {
+                bar.set_style(style.progress_chars("#>-"));
+            }
+            logs_bars.push(bar);
+        }
+
+        let mut ytdlp = Command::new("yt-dlp");
+        let path_file = self.opts.output.with_extension("path");
+        if self.opts.native {
+            // yt-dlp picks the extension; have it write the final path to a side file
+            ytdlp
+                .args(["--format", "bestaudio/best", "--output"])
+                .arg(self.opts.output.with_extension("%(ext)s"))
+                .args(["--print-to-file", "after_move:filepath"])
+                .arg(&path_file);
+        } else {
+            ytdlp
+                .args([
+                    "--extract-audio",
+                    "--audio-format",
+                    &self.opts.audio_format,
+                    "--output",
+                ])
+                .arg(&self.opts.output);
+        }
+        ytdlp.args(["--no-playlist", "--newline", url]);
+        let _ = fs::remove_file(&path_file);
+        let result = run_ytdlp_with_progress(&dl_bar, &logs_bars, &mut ytdlp);
+        dl_bar.finish_and_clear();
+        for bar in &logs_bars {
+            bar.finish_and_clear();
+        }
+        result.context("yt-dlp failed")?;
+
+        if !self.opts.native {
+            return Ok(self.opts.output.clone());
+        }
+        let printed = fs::read_to_string(&path_file);
+        let _ = fs::remove_file(&path_file);
+        let path = printed
+            .ok()
+            .and_then(|p| p.lines().last().map(PathBuf::from))
+            .filter(|p| p.is_file());
+        path.with_context(|| {
+            format!(
+                "yt-dlp did not report the downloaded file for {}",
+                self.opts.output.display()
+            )
+        })
+    }
+
+    /// Fetches the `yt-dlp -J` metadata of `url` behind a spinner in `mp`.
+    ///
+    /// # Errors
+    /// Fails when `yt-dlp` fails or prints invalid JSON.
+    pub fn fetch_metadata(mp: &ProgressGroup, url: &str) -> Result<Value> {
+        let json_spinner = add_spinner(mp, "Fetching video metadata");
+        let metadata = fetch_metadata_json(url);
+        json_spinner.finish_and_clear();
+        metadata
+    }
+}
+
+/// Runs `yt-dlp -J` for `url` and parses its output.
+///
+/// Passes `--no-playlist` like the download, so `watch?v=..&list=..` yields
+/// the video rather than its playlist.
+///
+/// # Errors
+/// Fails when `yt-dlp` fails or prints invalid JSON.
+pub fn fetch_metadata_json(url: &str) -> Result<Value> {
+    let output = run_output(Command::new("yt-dlp").args(["--no-playlist", "-J", url]))
+        .context("Failed to execute yt-dlp for JSON metadata")?;
+
+    if !output.status.success() {
+        bail!("yt-dlp -J returned non-zero exit code");
+    }
+
+    let json = serde_json::from_slice(&output.stdout).context("Invalid JSON from yt-dlp")?;
+    Ok(json)
+}
+
+fn run_ytdlp_with_progress(
+    pb: &ProgressBar,
+    logs: &[ProgressBar],
+    cmd: &mut Command,
+) -> Result<()> {
+    cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+    let child = spawn(cmd)?;
+
+    let (stdout, stderr) = {
+        let mut child = child.lock().unwrap_or_else(PoisonError::into_inner);
+        (
+            child.stdout.take().context("Failed to capture stdout")?,
+            child.stderr.take().context("Failed to capture stderr")?,
+        )
+    };
+
+    // yt-dlp prints progress lines like:
+    // "[download]  81.6% of   59.10MiB at    3.47MiB/s ETA 00:01"
+    let logs_buffer = Arc::new(Mutex::new(VecDeque::with_capacity(5)));
+    let pb_out = pb.clone();
+    let logs_out_vec: Vec<ProgressBar> = logs.to_vec();
+    let logs_out_buf = Arc::clone(&logs_buffer);
+    let out_handle = thread::spawn(move || {
+        let reader = BufReader::new(stdout);
+        for line in reader.lines().map_while(Result::ok) {
+            if let Some((permille, speed, _eta)) = parse_ytdlp_progress(&line) {
+                pb_out.set_length(1000);
+                pb_out.set_position(permille.min(1000));
+                let percent_int = permille / 10;
+                let percent_frac = permille % 10;
+                let fixed_spd = speed
+                    .as_deref()
+                    .map_or_else(|| "          ".to_string(), |s| format!("{s:>10}"));
+                let right = format!(" | {fixed_spd}");
+                pb_out.set_message(format!(
+                    "{percent_int}.{percent_frac}%\x1b[90m{right}\x1b[0m"
+                ));
+            } else {
+                // hide non-progress logs during download
+                pb_out.set_message("Downloading audio");
+                if let Ok(mut dq) = logs_out_buf.lock() {
+                    if dq.len() == 5 {
+                        dq.pop_front();
+                    }
+                    dq.push_back(line);
+                    // update log bars with last lines
+                    let lines: Vec<String> = dq.iter().cloned().collect();
+                    let start = if lines.len() > 5 { lines.len() - 5 } else { 0 };
+                    let slice = &lines[start..];
+                    // clear all first
+                    for b in &logs_out_vec {
+                        b.set_message(String::new());
+                    }
+                    for (i, l) in slice.iter().enumerate() {
+                        logs_out_vec[i].set_message(format!("\x1b[90m{l}\x1b[0m"));
+                    }
+                }
+            }
+        }
+    });
+
+    let pb_err = pb.clone();
+    let logs_err_vec: Vec<ProgressBar> = logs.to_vec();
+    let logs_err_buf = Arc::clone(&logs_buffer);
+    let err_handle = thread::spawn(move || {
+        let reader = BufReader::new(stderr);
+        for line in reader.lines().map_while(Result::ok) {
+            if let Some((permille, speed, _eta)) = parse_ytdlp_progress(&line) {
+                pb_err.set_length(1000);
+                pb_err.set_position(permille.min(1000));
+                let percent_int = permille / 10;
+                let percent_frac = permille % 10;
+                let fixed_spd = speed
+                    .as_deref()
+                    .map_or_else(|| "          ".to_string(), |s| format!("{s:>10}"));
+                let right = format!(" | {fixed_spd}");
+                pb_err.set_message(format!(
+                    "{percent_int}.{percent_frac}%\x1b[90m{right}\x1b[0m"
+                ));
+            } else {
+                // hide during success path, but keep logs for potential error reporting
+                pb_err.set_message("Downloading audio");
+                if let Ok(mut dq) = logs_err_buf.lock() {
+                    if dq.len() == 5 {
+                        dq.pop_front();
+                    }
+                    dq.push_back(line);
+                    let lines: Vec<String> = dq.iter().cloned().collect();
+                    let start = if lines.len() > 5 { lines.len() - 5 } else { 0 };
+                    let slice = &lines[start..];
+                    for b in &logs_err_vec {
+                        b.set_message(String::new());
+                    }
+                    for (i, l) in slice.iter().enumerate() {
+                        logs_err_vec[i].set_message(format!("\x1b[90m{l}\x1b[0m"));
+                    }
+                }
+            }
+        }
+        // no logs collected currently
+        Vec::<String>::new()
+    });
+
+    let status = wait(&child)?;
+    let _ = out_handle.join();
+    let err_logs = err_handle.join().unwrap_or_default();
+    if status.success() {
+        Ok(())
+    } else {
+        for line in err_logs {
+            eprintln!("{line}");
+        }
+        bail!("yt-dlp exited with status: {status}")
+    }
+}
+
+fn parse_ytdlp_progress(line: &str) -> Option<(u64, Option<String>, Option<String>)> {
+    if !line.starts_with("[download]") || !line.contains('%') {
+        return None;
+    }
+    // Percent with one decimal
+    let percent_part = line.split('%').next()?;
+    let token = percent_part.split_whitespace().last()?;
+    let mut it = token.split('.');
+    let whole = it.next()?;
+    let whole_num: u64 = whole.parse().ok()?;
+    let frac_digit: u64 = it
+        .next()
+        .and_then(|s| s.chars().next())
+        .and_then(|c| c.to_digit(10))
+        .map_or(0, u64::from);
+    let permille = whole_num
+        .saturating_mul(10)
+        .saturating_add(frac_digit)
+        .min(1000);
+
+    // Speed and ETA
+    let mut pieces = line.split_whitespace();
+    let mut speed: Option<String> = None;
+    let mut eta: Option<String> = None;
+    while let Some(word) = pieces.next() {
+        if word == "at"
+            && let Some(val) = pieces.next()
+        {
+            if val == "Unknown" {
+                // skip unit after Unknown if present
+                let _ = pieces.next();
+            } else {
+                let unit = pieces.next().unwrap_or("");
+                // some yt-dlp lines include trailing 'ETA' in the token stream; cut speed only
+                speed = Some(format!("{val} {unit}").replace(" ETA", ""));
+            }
+        }
+        if word == "ETA"
+            && let Some(val) = pieces.next()
+            && val != "Unknown"
+        {
+            eta = Some(val.to_string());
+        }
+    }
+
+    Some((permille, speed, eta))
+}
@@ -0,0 +1,165 @@
+//! Encoder selection for tracks that are re-encoded instead of stream-copied.
+
+/// Audio encoding settings.
+#[derive(Debug, Clone, Default)]
+pub struct EncodeOptions {
+    /// ffmpeg encoder; defaults to the usual encoder for the output extension
+    pub codec: Option<String>,
+    /// Target bitrate such as `192k`
+    pub bitrate: Option<String>,
+    /// Encoder-specific VBR quality passed as `-q:a` (e.g. `2` for libmp3lame)
+    pub quality: Option<String>,
+    /// Use the encoder's variable bitrate mode with a default quality
+    pub vbr: bool,
+}
+
+impl EncodeOptions {
+    /// Whether any setting was given explicitly (as opposed to format defaults).
+    #[must_use]
+    pub fn is_explicit(&self) -> bool {
+        self.codec.is_some() || self.bitrate.is_some() || self.quality.is_some() || self.vbr
+    }
+
+    /// Encoder used for files with extension `ext`.
+    #[must_use]
+    pub fn encoder(&self, ext: &str) -> Option<String> {
+        self.codec
+            .clone()
+            .or_else(|| default_encoder(ext).map(ToOwned::to_owned))
+    }
+
+    /// `ffmpeg` output arguments encoding audio for extension `ext`.
+    ///
+    /// Without an explicit bitrate or quality, lossy encoders get a sensible
+    /// default bitrate (or VBR quality with `vbr`); lossless ones get nothing.
+    #[must_use]
+    pub fn ffmpeg_args(&self, ext: &str) -> Vec<String> {
+        let mut args = Vec::new();
+        let encoder = self.encoder(ext);
+        if let Some(encoder) = &encoder {
+            args.extend(["-c:a".to_string(), encoder.clone()]);
+        }
+        let vbr = if self.vbr && self.quality.is_none() {
+            encoder.as_deref().map(vbr_args).unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        // Opus VBR still targets a bitrate; the other modes replace it
+        let uses_quality =
+            self.quality.is_some() || (!vbr.is_empty() && encoder.as_deref() != Some("libopus"));
+        if let Some(quality) = &self.quality {
+            args.extend(["-q:a".to_string(), quality.clone()]);
+        }
+        args.extend(vbr);
+        let bitrate = self.bitrate.clone().or_else(|| {
+            (!uses_quality)
+                .then(|| encoder.as_deref().and_then(default_bitrate))
+                .flatten()
+                .map(ToOwned::to_owned)
+        });
+        if let Some(bitrate) = bitrate {
+            args.extend(["-b:a".to_string(), bitrate]);
+        }
+        args
+    }
+}
+
+/// The usual ffmpeg encoder for an output extension.
+#[must_use]
+pub fn default_encoder(ext: &str) -> Option<&'static str> {
+    match ext.to_ascii_lowercase().as_str() {
+        "mp3" => Some("libmp3lame"),
+        "m4a" | "aac" | "mp4" => Some("aac"),
+        "opus" | "webm" => Some("libopus"),
+        "ogg" | "oga" => Some("libvorbis"),
+        "flac" => Some("flac"),
+        "wav" => Some("pcm_s16le"),
+        "alac" => Some("alac"),
+        _ => None,
+    }
+}
+
+/// Whether audio encoded with `codec` (as named by ffprobe) can be stream-copied
+/// into a file with extension `ext`.
+#[must_use]
+pub fn can_stream_copy(codec: &str, ext: &str) -> bool {
+    let ext = ext.to_ascii_lowercase();
+    let containers: &[&str] = match codec {
+        "mp3" => &["mp3", "mka"],
+        "aac" => &["aac", "m4a", "mp4", "mka"],
+        "opus" => &["opus", "ogg", "webm", "mka"],
+        "vorbis" => &["ogg", "oga", "webm", "mka"],
+        "flac" => &["flac", "ogg", "mka"],
+        "alac" => &["m4a", "mka"],
+        _ => &[],
+    };
+    containers.contains(&ext.as_str())
+}
+
+/// Arguments selecting VBR mode with a default quality for `encoder`.
+fn vbr_args(encoder: &str) -> Vec<String> {
+    let args: &[&str] = match encoder {
+        "libmp3lame" => &["-q:a", "2"],
+        "libvorbis" => &["-q:a", "6"],
+        "aac" => &["-q:a", "1.5"],
+        "libfdk_aac" => &["-vbr", "4"],
+        "libopus" => &["-vbr", "on"],
+        _ => &[],
+    };
+    args.iter().map(ToString::to_string).collect()
+}
+
+fn default_bitrate(encoder: &str) -> Option<&'static str> {
+    match encoder {
+        "libmp3lame" | "aac" | "libfdk_aac" | "libvorbis" => Some("192k"),
+        "libopus" => Some("160k"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(EncodeOptions::default(), "mp3", "-c:a libmp3lame -b:a 192k")]
+    #[case(EncodeOptions::default(), "flac", "-c:a flac")]
+    #[case(EncodeOptions::default(), "opus", "-c:a libopus -b:a 160k")]
+    #[case(
+        EncodeOptions { quality: Some("2".into()), ..EncodeOptions::default() },
+        "mp3",
+        "-c:a libmp3lame -q:a 2"
+    )]
+    #[case(
+        EncodeOptions { codec: Some("libfdk_aac".into()), bitrate: Some("256k".into()), ..EncodeOptions::default() },
+        "m4a",
+        "-c:a libfdk_aac -b:a 256k"
+    )]
+    #[case(
+        EncodeOptions { vbr: true, ..EncodeOptions::default() },
+        "mp3",
+        "-c:a libmp3lame -q:a 2"
+    )]
+    #[case(
+        EncodeOptions { vbr: true, ..EncodeOptions::default() },
+        "opus",
+        "-c:a libopus -vbr on -b:a 160k"
+    )]
+    #[case(EncodeOptions::default(), "xyz", "")]
+    fn test_ffmpeg_args(#[case] opts: EncodeOptions, #[case] ext: &str, #[case] expected: &str) {
+        assert_eq!(opts.ffmpeg_args(ext).join(" "), expected);
+    }
+
+    #[rstest]
+    #[case("opus", "opus", true)]
+    #[case("opus", "WEBM", true)]
+    #[case("opus", "mp3", false)]
+    #[case("aac", "m4a", true)]
+    #[case("aac", "aac", true)]
+    #[case("mp3", "mp3", true)]
+    #[case("pcm_s16le", "wav", false)]
+    fn test_can_stream_copy(#[case] codec: &str, #[case] ext: &str, #[case] expected: bool) {
+        assert_eq!(can_stream_copy(codec, ext), expected);
+    }
+}
@@ -0,0 +1,327 @@
+//! Recognising what the user passed as a video: a URL, a bare `YouTube` id or
+//! a yt-dlp search.
+
+use std::{fmt, net::IpAddr, str::FromStr};
+
+use anyhow::{Result, bail};
+
+use crate::batch::host_of;
+
+/// A video, playlist or search to hand to yt-dlp.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Input {
+    /// An `http(s)` URL of any site yt-dlp supports
+    Url(String),
+    /// A bare 11-character `YouTube` video id
+    VideoId(String),
+    /// A yt-dlp search such as `ytsearch5:query` or `scsearch:query`
+    Search { prefix: String, query: String },
+}
+
+impl Input {
+    /// The argument passed to yt-dlp.
+    #[must_use]
+    pub fn url(&self) -> String {
+        match self {
+            Self::Url(url) => url.clone(),
+            Self::VideoId(id) => format!("https://www.youtube.com/watch?v={id}"),
+            Self::Search { prefix, query } => format!("{prefix}:{query}"),
+        }
+    }
+}
+
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.url())
+    }
+}
+
+impl FromStr for Input {
+    type Err = anyhow::Error;
+
+    /// Accepts `http(s)://` URLs, URLs without a scheme (`youtu.be/abc`),
+    /// bare `YouTube` ids and `<site>search[N|all]:query`.
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if s.is_empty() {
+            bail!("empty input");
+        }
+        if let Some((prefix, query)) = s.split_once(':')
+            && is_search_prefix(prefix)
+        {
+            if query.trim().is_empty() {
+                bail!("'{prefix}:' needs a search query after the colon");
+            }
+            return Ok(Self::Search {
+                prefix: prefix.to_string(),
+                query: query.trim().to_string(),
+            });
+        }
+        if s.contains(char::is_whitespace) {
+            bail!("'{s}' contains spaces; search with 'ytsearch:{s}'");
+        }
+        if let Some((scheme, rest)) = s.split_once("://") {
+            if !matches!(scheme.to_ascii_lowercase().as_str(), "http" | "https") {
+                bail!("'{s}' uses unsupported scheme '{scheme}'; only http and https URLs work");
+            }
+            check_host(s, rest)?;
+            return Ok(Self::Url(s.to_string()));
+        }
+        if is_bare_video_id(s) {
+            return Ok(Self::VideoId(s.to_string()));
+        }
+        if check_host(s, s).is_ok() {
+            return Ok(Self::Url(format!("https://{s}")));
+        }
+        bail!(
+            "'{s}' is not a URL, an 11-character YouTube video id or a search like 'ytsearch:...'"
+        )
+    }
+}
+
+/// A line of a batch file: an input followed by `| key=value` options for it,
+/// e.g. `https://youtu.be/abc | format=flac | dest=albums/x`.
+///
+/// A bare `| key` means `key=true`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchLine {
+    pub input: Input,
+    /// Options in the order given; applied with [`crate::JobOptions::set_option`]
+    pub options: Vec<(String, String)>,
+}
+
+impl FromStr for BatchLine {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parts = s.split('|').map(str::trim);
+        let input = parts.next().unwrap_or_default().parse()?;
+        let mut options = Vec::new();
+        for part in parts {
+            let (key, value) = part.split_once('=').unwrap_or((part, "true"));
+            let key = key.trim();
+            if key.is_empty() {
+                bail!("option '{part}' has no name; write '| key=value'");
+            }
+            options.push((key.to_string(), value.trim().to_string()));
+        }
+        Ok(Self { input, options })
+    }
+}
+
+/// Key identifying the video behind `url`, to spot the same video given twice.
+///
+/// `YouTube` URLs of any form map to their video id; other URLs to themselves
+/// without scheme, fragment and trailing slash.
+#[must_use]
+pub fn video_key(url: &str) -> String {
+    if let Some(id) = youtube_id(url) {
+        return format!("youtube:{id}");
+    }
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let rest = rest.split('#').next().unwrap_or_default();
+    rest.trim_end_matches('/').to_string()
+}
+
+/// The video id of a `watch`, `shorts`, `live`, `embed` or `youtu.be` URL.
+fn youtube_id(url: &str) -> Option<&str> {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let path = &rest[rest.find('/')?..];
+    let id = match host_of(url).as_str() {
+        "youtu.be" => path[1..].split(['/', '?', '#']).next(),
+        "youtube.com" | "m.youtube.com" | "music.youtube.com" => {
+            if let Some(query) = path.strip_prefix("/watch?") {
+                query
+                    .split(['&', '#'])
+                    .find_map(|param| param.strip_prefix("v="))
+            } else {
+                ["/shorts/", "/live/", "/embed/"]
+                    .iter()
+                    .find_map(|prefix| path.strip_prefix(prefix))
+                    .and_then(|rest| rest.split(['/', '?', '#']).next())
+            }
+        }
+        _ => None,
+    }?;
+    is_video_id(id).then_some(id)
+}
+
+/// Checks the host at the start of `rest` (a URL without its scheme).
+fn check_host(input: &str, rest: &str) -> Result<()> {
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = authority.rsplit('@').next().unwrap_or_default();
+    // `[::1]:8080` keeps its colons inside the brackets
+    let host = match host.strip_prefix('[') {
+        Some(bracketed) => match bracketed.split_once(']') {
+            Some((ip, _)) => ip,
+            None => bail!("'{input}' has an unclosed '[' in its host"),
+        },
+        None => host.split(':').next().unwrap_or_default(),
+    };
+    if host.is_empty() {
+        bail!("'{input}' has no host");
+    }
+    if host.parse::<IpAddr>().is_ok() {
+        return Ok(());
+    }
+    if !host
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == '.' || c == '-')
+    {
+        bail!("'{input}' has an invalid host '{host}'");
+    }
+    // A fully qualified `example.com.` ends in a dot
+    let name = host.strip_suffix('.').unwrap_or(host);
+    let tld = name.rsplit('.').next().unwrap_or_default();
+    let valid_tld = (tld.len() >= 2 && tld.chars().all(char::is_alphabetic))
+        || tld.to_ascii_lowercase().starts_with("xn--");
+    if name != "localhost" && (!name.contains('.') || !valid_tld) {
+        bail!("'{input}' has no domain name in '{host}'");
+    }
+    Ok(())
+}
+
+/// `YouTube` ids are 11 characters of `[A-Za-z0-9_-]`.
+fn is_video_id(s: &str) -> bool {
+    s.len() == 11
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// A bare input taken as a `YouTube` id.
+///
+/// Any 11-character word like `hello-world` has the shape of an id, so a
+/// digit or an uppercase letter is also required; real ids without either
+/// are rare and can still be passed as `youtu.be/<id>`.
+fn is_bare_video_id(s: &str) -> bool {
+    is_video_id(s)
+        && s.chars()
+            .any(|c| c.is_ascii_digit() || c.is_ascii_uppercase())
+}
+
+/// Extensions of files that hold lists of inputs, never of URLs' hosts.
+const LIST_EXTENSIONS: &[&str] = &["txt", "list", "lst", "csv"];
+
+/// Whether `s` reads as a file path rather than a URL without scheme: it
+/// starts like a path (`/`, `./`, `~`), uses `\`, its first segment has no
+/// dot (`lists/urls`) or it ends in a list extension such as `.txt`.
+#[must_use]
+pub fn looks_like_path(s: &str) -> bool {
+    if s.contains("://")
+        || s.split_once(':')
+            .is_some_and(|(prefix, _)| is_search_prefix(prefix))
+    {
+        return false;
+    }
+    if s.starts_with(['/', '.', '~']) || s.contains('\\') {
+        return true;
+    }
+    if let Some((first, _)) = s.split_once('/')
+        && !first.contains('.')
+    {
+        return true;
+    }
+    let name = s.rsplit('/').next().unwrap_or_default();
+    name.rsplit_once('.')
+        .is_some_and(|(_, ext)| LIST_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// `ytsearch`, `ytsearch10`, `scsearchall`, ...
+fn is_search_prefix(prefix: &str) -> bool {
+    let Some((site, count)) = prefix.split_once("search") else {
+        return false;
+    };
+    !site.is_empty()
+        && site.chars().all(|c| c.is_ascii_lowercase())
+        && (count.is_empty() || count == "all" || count.chars().all(|c| c.is_ascii_digit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(
+        "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+        "https://www.youtube.com/watch?v=dQw4w9WgXcQ"
+    )]
+    #[case(
+        "http://soundcloud.com/artist/sets/mix",
+        "http://soundcloud.com/artist/sets/mix"
+    )]
+    #[case("dQw4w9WgXcQ", "https://www.youtube.com/watch?v=dQw4w9WgXcQ")]
+    #[case("youtu.be/dQw4w9WgXcQ", "https://youtu.be/dQw4w9WgXcQ")]
+    #[case("artist.bandcamp.com/album/x", "https://artist.bandcamp.com/album/x")]
+    #[case("ytsearch5: daft punk live", "ytsearch5:daft punk live")]
+    #[case("scsearch:ambient mix", "scsearch:ambient mix")]
+    #[case("https://192.168.1.5/mix.mp3", "https://192.168.1.5/mix.mp3")]
+    #[case("https://[::1]:8080/mix", "https://[::1]:8080/mix")]
+    #[case("https://musik.xn--p1ai/set", "https://musik.xn--p1ai/set")]
+    #[case("https://example.com./set", "https://example.com./set")]
+    #[case("http://localhost:8000/a", "http://localhost:8000/a")]
+    fn test_parse_input(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(input.parse::<Input>().unwrap().url(), expected);
+    }
+
+    #[rstest]
+    #[case("", "empty")]
+    #[case("ftp://example.com/a.mp3", "unsupported scheme")]
+    #[case("daft punk live", "contains spaces")]
+    #[case("ytsearch:", "needs a search query")]
+    #[case("https:///watch", "no host")]
+    #[case("not-a-link", "is not a URL")]
+    #[case("mixtape.mp3", "is not a URL")]
+    #[case("hello-world", "is not a URL")]
+    #[case("https://[::1/mix", "unclosed '['")]
+    #[case("https://localhost.1/x", "no domain name")]
+    fn test_parse_input_rejected(#[case] input: &str, #[case] reason: &str) {
+        let err = input.parse::<Input>().unwrap_err().to_string();
+        assert!(err.contains(reason), "{err}");
+    }
+
+    #[rstest]
+    #[case("urls.txt", true)]
+    #[case("lists/live.list", true)]
+    #[case("./urls", true)]
+    #[case("lists/urls", true)]
+    #[case(r"C:\lists\urls.csv", true)]
+    #[case("youtu.be/dQw4w9WgXcQ", false)]
+    #[case("artist.bandcamp.com/album/x", false)]
+    #[case("https://example.com/urls.txt", false)]
+    #[case("ytsearch:notes.txt", false)]
+    #[case("dQw4w9WgXcQ", false)]
+    fn test_looks_like_path(#[case] input: &str, #[case] expected: bool) {
+        assert_eq!(looks_like_path(input), expected);
+    }
+
+    #[test]
+    fn test_parse_batch_line() {
+        let line: BatchLine = "youtu.be/dQw4w9WgXcQ | prefix=live | format=flac,opus | numbers"
+            .parse()
+            .unwrap();
+        assert_eq!(line.input.url(), "https://youtu.be/dQw4w9WgXcQ");
+        assert_eq!(
+            line.options,
+            [
+                ("prefix".to_string(), "live".to_string()),
+                ("format".to_string(), "flac,opus".to_string()),
+                ("numbers".to_string(), "true".to_string()),
+            ]
+        );
+        assert!("dQw4w9WgXcQ | =x".parse::<BatchLine>().is_err());
+    }
+
+    #[rstest]
+    #[case(
+        "https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=42",
+        "youtube:dQw4w9WgXcQ"
+    )]
+    #[case("https://youtu.be/dQw4w9WgXcQ?si=x", "youtube:dQw4w9WgXcQ")]
+    #[case("https://m.youtube.com/shorts/dQw4w9WgXcQ", "youtube:dQw4w9WgXcQ")]
+    #[case("http://soundcloud.com/a/sets/b/", "soundcloud.com/a/sets/b")]
+    #[case("ytsearch:daft punk", "ytsearch:daft punk")]
+    fn test_video_key(#[case] url: &str, #[case] expected: &str) {
+        assert_eq!(video_key(url), expected);
+    }
+}
@@ -0,0 +1,128 @@
+//! Ctrl-C / SIGTERM handling.
+//!
+//! The handler only flags the interrupt and kills running `yt-dlp`/`ffmpeg`
+//! children. The pipeline then fails like on any other error, and on the way
+//! out partial tracks ([`PartialFiles`]) and work directories are removed.
+
+use std::{
+    fs,
+    path::PathBuf,
+    process,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use anyhow::{Context, Result};
+
+use crate::{process::kill_children, workdir::remove_active_work_dirs};
+
+/// Conventional exit code of a process stopped by SIGINT.
+pub const EXIT_INTERRUPTED: i32 = 130;
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Whether Ctrl-C or SIGTERM was received.
+#[must_use]
+pub fn is_interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+}
+
+/// Stops all work: no new children start and running ones are killed.
+pub fn interrupt() {
+    INTERRUPTED.store(true, Ordering::Relaxed);
+    kill_children();
+}
+
+/// Installs the Ctrl-C / SIGTERM handler.
+///
+/// The first signal calls [`interrupt`]; a second one removes the work
+/// directories of running jobs and exits immediately with [`EXIT_INTERRUPTED`].
+///
+/// # Errors
+/// Fails when a handler is already installed.
+pub fn install_handler() -> Result<()> {
+    ctrlc::set_handler(|| {
+        if is_interrupted() {
+            remove_active_work_dirs();
+            process::exit(EXIT_INTERRUPTED);
+        }
+        eprintln!("\n\x1b[33mInterrupted, stopping...\x1b[0m");
+        interrupt();
+    })
+    .context("Failed to install the Ctrl-C handler")
+}
+
+/// Output files being written; removed on drop unless [`PartialFiles::finish`] is called.
+#[derive(Debug, Default)]
+pub struct PartialFiles {
+    paths: Vec<PathBuf>,
+}
+
+impl PartialFiles {
+    #[must_use]
+    pub fn new(paths: impl IntoIterator<Item = PathBuf>) -> Self {
+        Self {
+            paths: paths.into_iter().collect(),
+        }
+    }
+
+    /// Marks the files complete so they are kept.
+    pub fn finish(mut self) {
+        self.paths.clear();
+    }
+}
+
+impl Drop for PartialFiles {
+    fn drop(&mut self) {
+        for path in &self.paths {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Serializes the tests that read or set the process-wide interrupt flag.
+#[cfg(test)]
+static FLAG_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// Holds the interrupt flag for one test: cleared now and again when dropped,
+/// so a test may call [`interrupt`] without affecting others.
+#[cfg(test)]
+pub(crate) struct FlagGuard {
+    _lock: std::sync::MutexGuard<'static, ()>,
+}
+
+#[cfg(test)]
+pub(crate) fn flag_guard() -> FlagGuard {
+    let guard = FLAG_LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    INTERRUPTED.store(false, Ordering::Relaxed);
+    FlagGuard { _lock: guard }
+}
+
+#[cfg(test)]
+impl Drop for FlagGuard {
+    fn drop(&mut self) {
+        INTERRUPTED.store(false, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_files() {
+        let dir = std::env::temp_dir().join(format!("slycer-partial-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let dropped = dir.join("dropped.mp3");
+        let finished = dir.join("finished.mp3");
+        fs::write(&dropped, b"half").unwrap();
+        fs::write(&finished, b"whole").unwrap();
+
+        drop(PartialFiles::new([dropped.clone()]));
+        PartialFiles::new([finished.clone()]).finish();
+        assert!(!dropped.exists());
+        assert!(finished.exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
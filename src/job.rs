@@ -0,0 +1,533 @@
+//! The full download → chapters → split pipeline for one URL.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{Result, bail};
+use serde_json::Value;
+
+use crate::{
+    archive::Archive,
+    chapters::{
+        ChapterContext, ChapterOptions, ChapterSource, load_chapter_file, resolve_chapters,
+    },
+    cover::{best_thumbnail, cover_path, fetch_cover},
+    cue::write_cue,
+    download::{DownloadOptions, Downloader},
+    encode::EncodeOptions,
+    metadata::VideoInfo,
+    naming::{Namer, NamingOptions},
+    playlist::{DateRange, PlaylistPosition},
+    probe::probe_media,
+    progress::{ProgressGroup, add_spinner},
+    split::{ErrorPolicy, OverwritePolicy, SplitOptions, SplitReport, Splitter, TrackHook},
+    template::sanitize_component,
+    workdir::{WorkDir, default_work_root, move_file},
+};
+
+/// Keys accepted by [`JobOptions::set_option`].
+pub const LINE_OPTIONS: &[&str] = &[
+    "format",
+    "dest",
+    "output",
+    "prefix",
+    "prefix-name",
+    "numbers",
+    "template",
+    "chapters",
+    "keep",
+    "album-from-playlist",
+];
+
+/// Everything needed to process a single URL.
+///
+/// Built from the command line by the binary, or directly in code.
+#[derive(Debug, Clone)]
+pub struct JobOptions {
+    /// Where `keep` puts the combined file; defaults to `<title> [<id>].<ext>`
+    /// in `dest`. The extension follows the downloaded audio.
+    pub output: Option<PathBuf>,
+    /// Parent of the per-job directories that hold downloads until the job
+    /// ends; defaults to `slycer` in the system temp directory
+    pub work_dir: Option<PathBuf>,
+    /// Formats of the produced tracks; the first is also the yt-dlp
+    /// extraction format. Each track is cut once and encoded to every format.
+    pub audio_formats: Vec<String>,
+    /// Download the native best-audio stream and encode tracks from it,
+    /// instead of transcoding the whole download first. Implied by more than
+    /// one format.
+    pub native: bool,
+    /// Destination directory for split tracks
+    pub dest: Option<PathBuf>,
+    /// Keep the downloaded combined audio file (do not delete after splitting)
+    pub keep: bool,
+    /// Write per-track tags taken from the video metadata
+    pub tags: bool,
+    /// Attach the video thumbnail as front cover
+    pub embed_cover: bool,
+    /// Center-crop the cover to a square
+    pub square_cover: bool,
+    /// Re-encode chapters for sample-accurate cuts
+    pub accurate: bool,
+    /// Encoder settings for re-encoded tracks
+    pub encode: EncodeOptions,
+    /// Chapters split in parallel; `0` uses one per CPU
+    pub jobs: usize,
+    /// Whether a failed chapter stops the others
+    pub on_error: ErrorPolicy,
+    /// What happens to tracks whose file already exists
+    pub overwrite: OverwritePolicy,
+    /// Write a CUE sheet next to the combined file; implies `keep`
+    pub cue: bool,
+    /// Produce split tracks (disable to only write the CUE sheet)
+    pub split: bool,
+    pub naming: NamingOptions,
+    pub chapters: ChapterOptions,
+    /// Record of finished videos and tracks; completed videos are skipped and
+    /// partially split ones resumed
+    pub archive: Option<Arc<Archive>>,
+    /// Convert each playlist video into one track numbered by its playlist
+    /// position, tagged with the playlist as album, instead of splitting it
+    pub album_from_playlist: bool,
+    /// Upload dates to process; videos outside the range are skipped
+    pub dates: DateRange,
+    /// Where the video sits in the playlist it was expanded from
+    pub playlist: Option<PlaylistPosition>,
+}
+
+impl Default for JobOptions {
+    fn default() -> Self {
+        Self {
+            output: None,
+            work_dir: None,
+            audio_formats: vec!["mp3".to_string()],
+            native: false,
+            dest: None,
+            keep: false,
+            tags: true,
+            embed_cover: false,
+            square_cover: false,
+            accurate: false,
+            encode: EncodeOptions::default(),
+            jobs: 0,
+            on_error: ErrorPolicy::Abort,
+            overwrite: OverwritePolicy::Overwrite,
+            cue: false,
+            split: true,
+            naming: NamingOptions::default(),
+            chapters: ChapterOptions::default(),
+            archive: None,
+            album_from_playlist: false,
+            dates: DateRange::default(),
+            playlist: None,
+        }
+    }
+}
+
+impl JobOptions {
+    /// Download settings writing to `output`.
+    #[must_use]
+    pub fn download_options(&self, output: PathBuf) -> DownloadOptions {
+        DownloadOptions {
+            audio_format: self.primary_format(),
+            output,
+            // Transcoding the download to one format would cost the others a generation
+            native: self.native || self.formats().len() > 1,
+        }
+    }
+
+    /// Split settings for the combined file `input`.
+    #[must_use]
+    pub fn split_options(&self, input: &Path) -> SplitOptions {
+        SplitOptions {
+            dest: self.dest.clone(),
+            tags: self.tags,
+            accurate: self.accurate,
+            encode: self.encode.clone(),
+            jobs: self.jobs,
+            on_error: self.on_error,
+            overwrite: self.overwrite,
+            ..SplitOptions::new(input)
+        }
+    }
+
+    /// Whether the combined file survives the job.
+    #[must_use]
+    pub fn keeps_combined(&self) -> bool {
+        self.keep || self.cue
+    }
+
+    /// Final location of the kept combined file `audio` of the video `info`.
+    #[must_use]
+    pub fn combined_path(&self, info: &VideoInfo, audio: &Path) -> PathBuf {
+        let ext = audio.extension().unwrap_or_default();
+        if let Some(output) = &self.output {
+            return output.with_extension(ext);
+        }
+        let title = info
+            .title
+            .as_deref()
+            .and_then(sanitize_component)
+            .unwrap_or_else(|| "audio".to_string());
+        let mut name = match info.id.as_deref().and_then(sanitize_component) {
+            Some(id) => format!("{title} [{id}]"),
+            None => title,
+        };
+        if !ext.is_empty() {
+            name = format!("{name}.{}", ext.to_string_lossy());
+        }
+        match &self.dest {
+            Some(dir) => dir.join(name),
+            None => PathBuf::from(name),
+        }
+    }
+
+    /// Requested formats in order, lowercased, without blanks or duplicates;
+    /// `mp3` when none is given.
+    #[must_use]
+    pub fn formats(&self) -> Vec<String> {
+        let mut formats: Vec<String> = Vec::new();
+        for format in &self.audio_formats {
+            let format = format.trim().to_ascii_lowercase();
+            if !format.is_empty() && !formats.contains(&format) {
+                formats.push(format);
+            }
+        }
+        if formats.is_empty() {
+            formats.push("mp3".to_string());
+        }
+        formats
+    }
+
+    /// Checks that the encoder settings fit the requested formats: an encoder
+    /// or a `-q:a` quality is specific to one format, so neither can be
+    /// combined with several.
+    ///
+    /// # Errors
+    /// Fails when `codec` or `quality` is set along with more than one format.
+    pub fn check_encode(&self) -> Result<()> {
+        let formats = self.formats();
+        if formats.len() > 1 {
+            let flag = if self.encode.codec.is_some() {
+                "--codec"
+            } else if self.encode.quality.is_some() {
+                "--quality"
+            } else {
+                return Ok(());
+            };
+            bail!(
+                "{flag} applies to a single format, but {} were requested; use --bitrate or --vbr, or one format per run",
+                formats.join(", ")
+            );
+        }
+        Ok(())
+    }
+
+    /// The first requested format.
+    #[must_use]
+    pub fn primary_format(&self) -> String {
+        self.formats().swap_remove(0)
+    }
+
+    /// One namer per requested format.
+    #[must_use]
+    pub fn namers(&self) -> Vec<Namer> {
+        self.formats()
+            .into_iter()
+            .map(|format| Namer::new(self.naming.clone(), format))
+            .collect()
+    }
+
+    /// Overrides the option `key` (one of [`LINE_OPTIONS`]) with `value`, as
+    /// given on a batch file line. Paths are relative to the working directory.
+    ///
+    /// # Errors
+    /// Fails on an unknown key, an invalid value or a chapters file that is
+    /// missing or can't be parsed.
+    pub fn set_option(&mut self, key: &str, value: &str) -> Result<()> {
+        match key {
+            "format" => {
+                self.audio_formats = value.split(',').map(ToOwned::to_owned).collect();
+            }
+            "dest" => self.dest = Some(PathBuf::from(value)),
+            "output" => self.output = Some(PathBuf::from(value)),
+            "prefix" => self.naming.prefix = Some(value.to_string()),
+            "prefix-name" => self.naming.prefix_name = parse_flag(key, value)?,
+            "numbers" => self.naming.numbers = parse_flag(key, value)?,
+            "template" => self.naming.template = Some(value.parse()?),
+            "chapters" => {
+                let path = PathBuf::from(value);
+                // Parse now so a bad file skips the line instead of failing its video
+                load_chapter_file(&path, None)?;
+                self.chapters = ChapterOptions {
+                    sources: vec![ChapterSource::File(path)],
+                };
+            }
+            "keep" => self.keep = parse_flag(key, value)?,
+            "album-from-playlist" => self.album_from_playlist = parse_flag(key, value)?,
+            _ => bail!(
+                "unknown option '{key}'; expected one of {}",
+                LINE_OPTIONS.join(", ")
+            ),
+        }
+        Ok(())
+    }
+}
+
+fn parse_flag(key: &str, value: &str) -> Result<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "yes" | "1" => Ok(true),
+        "false" | "no" | "0" => Ok(false),
+        _ => bail!("option '{key}' takes true or false, not '{value}'"),
+    }
+}
+
+/// Downloads `url` and splits it into tracks according to `opts`.
+///
+/// The download lives in a private work directory that is removed when the
+/// job ends, whether it succeeds or not; `keep` moves it out first.
+///
+/// # Errors
+/// Fails when downloading, fetching metadata or running `ffmpeg` fails.
+pub fn download_and_split(mp: &ProgressGroup, opts: &JobOptions, url: &str) -> Result<SplitReport> {
+    if let Some(archive) = &opts.archive
+        && archive.is_complete_url(url)
+    {
+        mp.println(format!("\x1b[90mSkipping {url}: already in archive\x1b[0m"));
+        return Ok(SplitReport::default());
+    }
+
+    let metadata = Downloader::fetch_metadata(mp, url)?;
+    let mut info = VideoInfo::from_metadata(&metadata);
+    info.webpage_url.get_or_insert_with(|| url.to_string());
+    if let Some(position) = &opts.playlist {
+        info.playlist_index = Some(position.index);
+        info.playlist_title.clone_from(&position.title);
+        info.playlist_count = Some(position.count);
+        info.playlist_uploader.clone_from(&position.uploader);
+    }
+    if let Some(date) = &info.upload_date
+        && !opts.dates.contains(date)
+    {
+        mp.println(format!(
+            "\x1b[90mSkipping {url}: uploaded {date}, outside the date range\x1b[0m"
+        ));
+        return Ok(SplitReport::default());
+    }
+
+    let archive_key = info.id.clone().unwrap_or_else(|| url.to_string());
+    let record = opts.archive.as_ref().and_then(|a| a.get(&archive_key));
+    if record.as_ref().is_some_and(|r| r.complete) {
+        mp.println(format!("\x1b[90mSkipping {url}: already in archive\x1b[0m"));
+        return Ok(SplitReport::default());
+    }
+    if let Some(archive) = &opts.archive {
+        archive.start(&archive_key, url)?;
+    }
+
+    let work_root = opts.work_dir.clone().unwrap_or_else(default_work_root);
+    let work = WorkDir::create(&work_root, info.id.as_deref().unwrap_or("video"))?;
+    let stem = info.id.as_deref().and_then(sanitize_component);
+    let output = work.file(&format!(
+        "{}.{}",
+        stem.as_deref().unwrap_or("audio"),
+        opts.primary_format()
+    ));
+    let audio = Downloader::new(opts.download_options(output)).download(mp, url)?;
+
+    let ctx = ChapterContext::new(&metadata, Some(&audio));
+    let chapters = resolve_chapters(&opts.chapters, &ctx)?;
+
+    let mut report = SplitReport::default();
+    if opts.split {
+        let cover = if opts.embed_cover {
+            prepare_cover(mp, opts, &info, &cover_path(&audio))
+        } else {
+            None
+        };
+
+        let namers = opts.namers();
+        let splitter = Splitter::new(SplitOptions {
+            cover,
+            // Lets a native download be copied into tracks of the same codec
+            source_codec: probe_media(&audio).ok().and_then(|p| p.audio_codec),
+            resume: record
+                .map(|r| r.tracks.into_iter().collect())
+                .unwrap_or_default(),
+            on_track: opts
+                .archive
+                .clone()
+                .map(|archive| archive_hook(mp, archive, archive_key.clone())),
+            ..opts.split_options(&audio)
+        });
+
+        if opts.album_from_playlist && opts.playlist.is_some() {
+            report = splitter.convert_playlist_track_to(mp, &namers, &info)?;
+        } else if chapters.is_empty() {
+            // No chapters: convert entire video to a single track
+            report = splitter.convert_whole_to(mp, &namers, &info)?;
+        } else {
+            report = splitter.split_to(mp, &chapters, &namers, &info)?;
+        }
+    }
+
+    if opts.keeps_combined() {
+        let kept = opts.combined_path(&info, &audio);
+        move_file(&audio, &kept)?;
+        if opts.cue {
+            write_cue(&kept.with_extension("cue"), &kept, &info, &chapters)?;
+        }
+    }
+    // A `--no-split` run produced no tracks, so a later run may still split it
+    if opts.split
+        && let Some(archive) = &opts.archive
+    {
+        archive.complete(&archive_key)?;
+    }
+    Ok(report)
+}
+
+/// Records each finished track in `archive`; a failed save only warns.
+fn archive_hook(mp: &ProgressGroup, archive: Arc<Archive>, key: String) -> TrackHook {
+    let mp = mp.clone();
+    TrackHook::new(move |tracks| {
+        if let Err(err) = archive.add_tracks(&key, tracks) {
+            mp.println(format!("\x1b[33m{err:#}\x1b[0m"));
+        }
+    })
+}
+
+/// Splits the local audio file `input` into tracks without downloading.
+///
+/// Chapters come from `opts.chapters`; tags are taken from the file's own
+/// tags. Tracks keep the input's format. `output`, `work_dir`,
+/// `audio_formats`, `keep` and the cover options of `opts` are ignored.
+///
+/// # Errors
+/// Fails when the file cannot be probed, has no chapters or `ffmpeg` fails.
+pub fn split_file(mp: &ProgressGroup, opts: &JobOptions, input: &Path) -> Result<SplitReport> {
+    let probe = probe_media(input)?;
+    let info = probe.video_info(input);
+
+    let ctx = ChapterContext {
+        metadata: &Value::Null,
+        media: Some(input),
+        duration: probe.duration,
+    };
+    let chapters = resolve_chapters(&opts.chapters, &ctx)?;
+    if chapters.is_empty() {
+        bail!(
+            "No chapters for {}: pass --chapters or use a file with embedded chapters",
+            input.display()
+        );
+    }
+
+    let ext = input.extension().map_or_else(
+        || opts.primary_format(),
+        |e| e.to_string_lossy().into_owned(),
+    );
+    let namer = Namer::new(opts.naming.clone(), ext);
+    let splitter = Splitter::new(SplitOptions {
+        source_codec: probe.audio_codec.clone(),
+        ..opts.split_options(input)
+    });
+    splitter.split(mp, &chapters, &namer, &info)
+}
+
+/// Fetches the cover once per video into `path`. A missing or broken thumbnail only warns.
+fn prepare_cover(
+    mp: &ProgressGroup,
+    opts: &JobOptions,
+    info: &VideoInfo,
+    path: &Path,
+) -> Option<PathBuf> {
+    let Some(url) = best_thumbnail(info) else {
+        mp.println("\x1b[90mNo thumbnail available, skipping cover\x1b[0m");
+        return None;
+    };
+    let spinner = add_spinner(mp, "Fetching cover art");
+    let result = fetch_cover(url, path, opts.square_cover);
+    spinner.finish_and_clear();
+    match result {
+        Ok(()) => Some(path.to_path_buf()),
+        Err(err) => {
+            mp.println(format!("\x1b[33mSkipping cover: {err:#}\x1b[0m"));
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multiple_formats() {
+        let opts = JobOptions {
+            audio_formats: vec!["MP3".into(), "opus".into(), String::new(), "mp3".into()],
+            ..JobOptions::default()
+        };
+        assert_eq!(opts.formats(), ["mp3", "opus"]);
+        assert_eq!(opts.primary_format(), "mp3");
+        assert_eq!(opts.namers().len(), 2);
+        assert!(opts.download_options(PathBuf::from("a.mp3")).native);
+        assert!(
+            !JobOptions::default()
+                .download_options(PathBuf::from("a.mp3"))
+                .native
+        );
+    }
+
+    #[test]
+    fn test_combined_path() {
+        let info = VideoInfo {
+            id: Some("abc123".into()),
+            title: Some("Live: Set".into()),
+            ..VideoInfo::default()
+        };
+        let audio = Path::new("/tmp/slycer/abc123-1-0/abc123.webm");
+        let opts = JobOptions {
+            dest: Some(PathBuf::from("tracks")),
+            ..JobOptions::default()
+        };
+        assert_eq!(
+            opts.combined_path(&info, audio),
+            Path::new("tracks/Live_ Set [abc123].webm")
+        );
+        let opts = JobOptions {
+            output: Some(PathBuf::from("out.mp3")),
+            ..opts
+        };
+        assert_eq!(opts.combined_path(&info, audio), Path::new("out.webm"));
+    }
+
+    #[test]
+    fn test_set_option() {
+        let mut opts = JobOptions::default();
+        opts.set_option("format", "flac,opus").unwrap();
+        opts.set_option("dest", "albums/x").unwrap();
+        opts.set_option("numbers", "true").unwrap();
+        assert_eq!(opts.formats(), ["flac", "opus"]);
+        assert_eq!(opts.dest.as_deref(), Some(Path::new("albums/x")));
+        assert!(opts.naming.numbers);
+
+        assert!(opts.set_option("numbers", "maybe").is_err());
+        assert!(opts.set_option("colour", "red").is_err());
+        assert!(opts.check_encode().is_ok());
+        opts.encode.quality = Some("2".into());
+        assert!(opts.check_encode().is_err());
+        opts.set_option("format", "mp3").unwrap();
+        assert!(opts.check_encode().is_ok());
+        assert!(
+            opts.set_option("chapters", "/nonexistent/chapters.txt")
+                .is_err()
+        );
+        let path = std::env::temp_dir().join(format!("slycer-line-{}.txt", std::process::id()));
+        std::fs::write(&path, "no timestamps here\n").unwrap();
+        let result = opts.set_option("chapters", &path.to_string_lossy());
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,43 @@
+//! Download audio with `yt-dlp` and split it into chapter tracks with `ffmpeg`.
+//!
+//! The pipeline is made of four pieces, each configured by a plain options struct:
+//! a [`Downloader`] fetches the combined audio and metadata, a [`ChapterSource`]
+//! yields chapter boundaries, a [`Namer`] builds output filenames and a
+//! [`Splitter`] cuts the tracks. [`download_and_split`] runs the whole pipeline
+//! for one URL as described by [`JobOptions`].
+
+pub mod archive;
+pub mod batch;
+pub mod chapters;
+pub mod cover;
+pub mod cue;
+pub mod deps;
+pub mod download;
+pub mod encode;
+pub mod input;
+pub mod interrupt;
+pub mod job;
+pub mod metadata;
+pub mod naming;
+pub mod playlist;
+pub mod probe;
+mod process;
+pub mod progress;
+pub mod split;
+pub mod tags;
+pub mod template;
+pub mod workdir;
+
+pub use archive::Archive;
+pub use batch::{BatchJob, BatchOptions, BatchReport, run_batch};
+pub use chapters::{Chapter, ChapterContext, ChapterOptions, ChapterSource, resolve_chapters};
+pub use download::{DownloadOptions, Downloader};
+pub use encode::EncodeOptions;
+pub use input::{BatchLine, Input};
+pub use job::{JobOptions, download_and_split, split_file};
+pub use metadata::VideoInfo;
+pub use naming::{Namer, NamingOptions};
+pub use playlist::{PlaylistOptions, expand_url, expand_urls};
+pub use split::{ErrorPolicy, OverwritePolicy, SplitOptions, SplitReport, Splitter, TrackHook};
+pub use tags::TrackTags;
+pub use template::Template;
@@ -1,55 +1,171 @@
 use std::{
-    collections::VecDeque,
-    fs,
-    io::{self, BufRead, BufReader, Write},
+    collections::HashMap,
+    fs, io,
     path::{Path, PathBuf},
-    process::{Command, Stdio},
-    sync::{Arc, Mutex},
-    thread,
+    process,
+    sync::Arc,
     time::Duration,
 };
 
 use anyhow::{Context, Result, bail};
-use clap::Parser;
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
-use serde::Deserialize;
-use serde_json::Value;
-use which::which;
-
-#[derive(Debug, Deserialize)]
-struct Chapter {
-    title: String,
-    start_time: f64,
-    end_time: f64,
-}
+use clap::{Args, Parser, Subcommand};
+use indicatif::MultiProgress;
+use slycer::{
+    Archive, BatchJob, BatchLine, BatchOptions, ChapterOptions, ChapterSource, EncodeOptions,
+    ErrorPolicy, Input, JobOptions, NamingOptions, OverwritePolicy, PlaylistOptions, SplitReport,
+    Template,
+    chapters::load_chapter_file,
+    deps::{DOWNLOAD_BINARIES, SPLIT_BINARIES, ensure_binaries_present},
+    download_and_split, expand_urls,
+    input::{looks_like_path, video_key},
+    interrupt::{EXIT_INTERRUPTED, install_handler, is_interrupted},
+    playlist::{DateRange, PlaylistItems, parse_date},
+    progress::{ProgressGroup, add_counter, add_spinner},
+    run_batch, split_file,
+};
 
 #[derive(Debug, Parser)]
 #[command(
     name = "slycer",
     version,
-    about = "Download and split YouTube audio by chapters"
+    about = "Download and split audio from YouTube and other yt-dlp sites by chapters",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
 )]
-#[allow(clippy::struct_excessive_bools)]
 struct Cli {
-    /// `YouTube` video URL
-    input: String,
+    #[command(subcommand)]
+    command: Option<Commands>,
 
-    /// Output audio file path
-    #[arg(short = 'o', long = "output", default_value = "out.mp3")]
-    output: PathBuf,
+    /// Video, playlist or channel URLs (any site yt-dlp supports), bare YouTube
+    /// video ids, `ytsearch:` queries, files with one of these per line, or `-`
+    /// to read them from stdin
+    #[arg(required = true, value_name = "INPUT")]
+    inputs: Vec<String>,
 
-    /// Audio format for yt-dlp extraction
-    #[arg(short = 'f', long = "audio-format", default_value = "mp3")]
-    audio_format: String,
+    #[command(flatten)]
+    download: DownloadArgs,
 
-    /// Auto-approve installing missing dependencies (`yt-dlp`, `ffmpeg`)
-    #[arg(short = 'y', long = "yes", default_value_t = false)]
-    yes: bool,
+    #[command(flatten)]
+    common: CommonArgs,
+}
+
+#[derive(Debug, Subcommand)]
+enum Commands {
+    /// Split a local audio file by chapters without downloading
+    Split(SplitArgs),
+}
+
+#[derive(Debug, Args)]
+struct SplitArgs {
+    /// Audio file to split; chapters come from --chapters or the file itself
+    file: PathBuf,
+
+    #[command(flatten)]
+    common: CommonArgs,
+}
+
+/// Options only meaningful when downloading.
+#[derive(Debug, Args)]
+#[allow(clippy::struct_excessive_bools)]
+struct DownloadArgs {
+    /// Where --keep puts the combined file (default: "<title> [<id>].<ext>" in --dest)
+    #[arg(short = 'o', long = "output")]
+    output: Option<PathBuf>,
+
+    /// Directory for in-progress downloads (default: system temp directory)
+    #[arg(long = "work-dir")]
+    work_dir: Option<PathBuf>,
+
+    /// Audio format(s) of the tracks, comma-separated (e.g. `mp3,opus,flac`)
+    #[arg(
+        short = 'f',
+        long = "audio-format",
+        default_value = "mp3",
+        value_delimiter = ','
+    )]
+    audio_formats: Vec<String>,
+
+    /// Put each format's tracks in its own subdirectory (`mp3/`, `opus/`)
+    #[arg(long = "format-dirs", default_value_t = false)]
+    format_dirs: bool,
+
+    /// Download the best audio stream as served and encode each track from it
+    /// (avoids transcoding twice; the combined file keeps its native format)
+    #[arg(long = "native", default_value_t = false)]
+    native: bool,
 
     /// Keep the downloaded combined audio file (do not delete after splitting)
     #[arg(short = 'k', long = "keep", default_value_t = false)]
     keep: bool,
 
+    /// Embed the video thumbnail as front cover (mp3, m4a, flac)
+    #[arg(long = "embed-cover", default_value_t = false)]
+    embed_cover: bool,
+
+    /// Crop the embedded cover to a square
+    #[arg(
+        long = "square-cover",
+        default_value_t = false,
+        requires = "embed_cover"
+    )]
+    square_cover: bool,
+
+    /// Write a CUE sheet next to the combined file (implies --keep)
+    #[arg(long = "cue", default_value_t = false)]
+    cue: bool,
+
+    /// Don't split; only write the CUE sheet
+    #[arg(long = "no-split", default_value_t = false, requires = "cue")]
+    no_split: bool,
+
+    /// Record finished videos and tracks in FILE; reruns skip them and resume
+    /// partially split videos
+    #[arg(long = "archive", value_name = "FILE")]
+    archive: Option<PathBuf>,
+
+    /// Playlist entries to process, by position, e.g. `1-3,7,10-`
+    #[arg(long = "playlist-items", value_name = "ITEMS")]
+    playlist_items: Option<PlaylistItems>,
+
+    /// Treat each playlist as an album: every video becomes one track numbered
+    /// by its playlist position, tagged with the playlist as album
+    #[arg(long = "album-from-playlist", default_value_t = false)]
+    album_from_playlist: bool,
+
+    /// Process at most N videos of each playlist or channel. Videos whose upload date the
+    /// listing doesn't show count even if the date filter skips them later
+    #[arg(long = "max-items", value_name = "N")]
+    max_items: Option<usize>,
+
+    /// Only process videos uploaded on or after DATE (`YYYYMMDD` or `YYYY-MM-DD`)
+    #[arg(long = "date-after", value_name = "DATE", value_parser = parse_date)]
+    date_after: Option<String>,
+
+    /// Only process videos uploaded on or before DATE (`YYYYMMDD` or `YYYY-MM-DD`)
+    #[arg(long = "date-before", value_name = "DATE", value_parser = parse_date)]
+    date_before: Option<String>,
+
+    /// Number of videos of a batch file or playlist processed at the same time
+    #[arg(short = 'p', long = "parallel", default_value_t = 1)]
+    parallel: usize,
+
+    /// Maximum URLs from the same host processed at the same time
+    #[arg(long = "per-host", default_value_t = 2)]
+    per_host: usize,
+
+    /// Seconds to wait between starting two URLs from the same host (only with `--parallel`)
+    #[arg(long = "host-delay", value_name = "SECS", default_value = "1", value_parser = parse_delay)]
+    host_delay: Duration,
+}
+
+/// Options shared by downloading and local splitting.
+#[derive(Debug, Args)]
+#[allow(clippy::struct_excessive_bools)]
+struct CommonArgs {
+    /// Auto-approve installing missing dependencies (`yt-dlp`, `ffmpeg`)
+    #[arg(short = 'y', long = "yes", default_value_t = false)]
+    yes: bool,
+
     /// Destination directory for split tracks
     #[arg(short = 'd', long = "dest")]
     dest: Option<PathBuf>,
@@ -65,815 +181,443 @@ struct Cli {
     /// Use video title (processed) as prefix
     #[arg(long = "prefix-name", default_value_t = false)]
     prefix_name: bool,
-}
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
+    /// Filename template, e.g. `{uploader}/{video_title}/{n:02} {chapter}`
+    /// (overrides --prefix, --prefix-name and --numbers)
+    #[arg(short = 't', long = "template")]
+    template: Option<Template>,
+
+    /// Chapter list overriding the video's own chapters: `timestamp title` lines,
+    /// a CUE sheet, an ffmetadata file or a JSON array of chapters
+    #[arg(short = 'c', long = "chapters")]
+    chapters: Option<PathBuf>,
+
+    /// Do not write per-track tags (title, album, artist, track, date, source URL)
+    #[arg(long = "no-tags", default_value_t = false)]
+    no_tags: bool,
+
+    /// Re-encode each chapter for sample-accurate cuts (slower than the default stream copy)
+    #[arg(long = "accurate", default_value_t = false)]
+    accurate: bool,
+
+    /// Number of chapters to split in parallel (default: number of CPUs)
+    #[arg(
+        short = 'j',
+        long = "jobs",
+        default_value_t = 0,
+        hide_default_value = true
+    )]
+    jobs: usize,
+
+    /// Keep splitting the other chapters when one fails
+    #[arg(long = "keep-going", default_value_t = false)]
+    keep_going: bool,
+
+    /// What to do with a track whose file already exists: overwrite, skip or
+    /// rename (write `name (2).ext`)
+    #[arg(long = "overwrite", value_name = "POLICY", default_value_t = OverwritePolicy::Overwrite)]
+    overwrite: OverwritePolicy,
+
+    /// Leave existing tracks alone; same as `--overwrite skip`
+    #[arg(
+        long = "skip-existing",
+        default_value_t = false,
+        conflicts_with = "overwrite"
+    )]
+    skip_existing: bool,
 
-    ensure_binaries_present(cli.yes)?;
+    /// ffmpeg encoder for re-encoded and converted tracks (default depends on the format, e.g. `libmp3lame`)
+    #[arg(long = "codec")]
+    codec: Option<String>,
 
-    let mp = MultiProgress::new();
+    /// Bitrate for re-encoded and converted tracks, e.g. `256k`
+    #[arg(long = "bitrate", conflicts_with = "quality")]
+    bitrate: Option<String>,
 
-    // Resolve input: file with URLs or single URL
-    let maybe_path = Path::new(&cli.input);
-    if maybe_path.is_file() {
-        // batch mode
-        let content = fs::read_to_string(maybe_path).context("Failed to read input file")?;
-        let urls: Vec<String> = content
-            .lines()
-            .map(str::trim)
-            .filter(|l| !l.is_empty() && !l.starts_with('#'))
-            .map(ToOwned::to_owned)
-            .collect();
-        if urls.is_empty() {
-            bail!("Input file contains no URLs");
-        }
+    /// Encoder VBR quality for re-encoded and converted tracks (`-q:a`), e.g. `2` for mp3
+    #[arg(long = "quality")]
+    quality: Option<String>,
 
-        // Filter only valid https:// links; log invalid lines in red
-        let mut valid_urls: Vec<String> = Vec::new();
-        let overall = mp.add(ProgressBar::new(u64::try_from(urls.len()).unwrap_or(0)));
-        if let Ok(style) = ProgressStyle::with_template(
-            "{spinner:.green} \x1b[90m{elapsed_precise}\x1b[0m [{bar:40.cyan/blue}] {pos}/{len} {msg}",
-        ) {
-            overall.set_style(style.progress_chars("#>-"));
-        }
-        overall.set_message("Processing URLs");
-        overall.set_position(0);
-        overall.enable_steady_tick(Duration::from_millis(100));
+    /// Use variable bitrate with the encoder's default quality
+    #[arg(long = "vbr", default_value_t = false, conflicts_with = "bitrate")]
+    vbr: bool,
+}
 
-        for line in urls {
-            if line.starts_with("https://") {
-                valid_urls.push(line);
+impl CommonArgs {
+    /// Job options for these flags, with download options left at their defaults.
+    fn job_options(&self, fallback: ChapterOptions) -> JobOptions {
+        JobOptions {
+            dest: self.dest.clone(),
+            tags: !self.no_tags,
+            accurate: self.accurate,
+            jobs: self.jobs,
+            on_error: if self.keep_going {
+                ErrorPolicy::Continue
             } else {
-                overall.println(format!("\x1b[31mSkipping invalid URL: {line}\x1b[0m"));
-                overall.inc(1);
-            }
+                ErrorPolicy::Abort
+            },
+            overwrite: if self.skip_existing {
+                OverwritePolicy::Skip
+            } else {
+                self.overwrite
+            },
+            encode: EncodeOptions {
+                codec: self.codec.clone(),
+                bitrate: self.bitrate.clone(),
+                quality: self.quality.clone(),
+                vbr: self.vbr,
+            },
+            naming: NamingOptions {
+                prefix: self.prefix.clone(),
+                numbers: self.numbers,
+                prefix_name: self.prefix_name,
+                template: self.template.clone(),
+                format_dirs: false,
+            },
+            chapters: match &self.chapters {
+                Some(path) => ChapterOptions {
+                    sources: vec![ChapterSource::File(path.clone())],
+                },
+                None => fallback,
+            },
+            ..JobOptions::default()
         }
+    }
 
-        for url in valid_urls {
-            match download_and_split(&mp, &cli, &url) {
-                Ok(()) => {}
-                Err(err) => {
-                    overall.println(format!("\x1b[31m{url}: {err}\x1b[0m"));
-                }
-            }
-            overall.inc(1);
+    /// Fails on a broken chapters file before any work starts.
+    fn check_chapters(&self) -> Result<()> {
+        if let Some(path) = &self.chapters {
+            load_chapter_file(path, None)?;
         }
-        overall.finish_with_message("All done");
         Ok(())
-    } else {
-        // single URL
-        download_and_split(&mp, &cli, &cli.input)
     }
 }
 
-#[allow(clippy::too_many_lines)]
-fn download_and_split(mp: &MultiProgress, cli: &Cli, url: &str) -> Result<()> {
-    // Download progress bar (starts as bar; will remain bar even if no percent)
-    let dl_bar = mp.add(ProgressBar::new(1000));
-    if let Ok(style) = ProgressStyle::with_template(
-        "{spinner:.green} \x1b[90m{elapsed_precise}\x1b[0m [{bar:40.cyan/blue}] {msg}",
-    ) {
-        dl_bar.set_style(style.progress_chars("#>-"));
-    }
-    dl_bar.set_message("Downloading audio");
-    dl_bar.set_position(0);
-    dl_bar.enable_steady_tick(Duration::from_millis(100));
-
-    // Logs bars: show last 5 non-progress lines (each on its own line, gray, no spinners)
-    let mut logs_bars: Vec<ProgressBar> = Vec::with_capacity(5);
-    for _ in 0..5 {
-        let bar = mp.add(ProgressBar::new(0));
-        if let Ok(style) = ProgressStyle::with_template("\x1b[90m{msg}\x1b[0m") {
-            bar.set_style(style.progress_chars("#>-"));
+impl DownloadArgs {
+    fn batch_options(&self) -> BatchOptions {
+        BatchOptions {
+            parallel: self.parallel,
+            per_host: self.per_host,
+            host_interval: self.host_delay,
         }
-        logs_bars.push(bar);
     }
 
-    // Build yt-dlp command
-    let mut ytdlp = Command::new("yt-dlp");
-    ytdlp.args([
-        "--extract-audio",
-        "--audio-format",
-        &cli.audio_format,
-        "--no-playlist",
-        "--newline",
-        "--output",
-        &cli.output.to_string_lossy(),
-        url,
-    ]);
-    run_ytdlp_with_progress(&dl_bar, &logs_bars, &mut ytdlp).context("yt-dlp failed")?;
-    dl_bar.finish_and_clear();
-    for bar in &logs_bars {
-        bar.finish_and_clear();
-    }
-
-    // Metadata
-    let json_spinner = mp.add(ProgressBar::new_spinner());
-    if let Ok(style) =
-        ProgressStyle::with_template("{spinner:.green} \x1b[90m{elapsed_precise}\x1b[0m {msg}")
-    {
-        json_spinner.set_style(style.progress_chars("#>-"));
-    }
-    json_spinner.enable_steady_tick(Duration::from_millis(100));
-    json_spinner.set_message("Fetching video metadata");
-    let metadata = fetch_metadata_json(url)?;
-    json_spinner.finish_and_clear();
-    // no top white logs
-
-    let chapters = extract_chapters(&metadata).unwrap_or_default();
-
-    if chapters.is_empty() {
-        // No chapters: convert entire video to single MP3
-        let convert_bar = mp.add(ProgressBar::new_spinner());
-        if let Ok(style) =
-            ProgressStyle::with_template("{spinner:.green} \x1b[90m{elapsed_precise}\x1b[0m {msg}")
-        {
-            convert_bar.set_style(style.progress_chars("#>-"));
+    fn playlist_options(&self) -> PlaylistOptions {
+        PlaylistOptions {
+            items: self.playlist_items.clone(),
+            max_items: self.max_items,
+            dates: self.dates(),
         }
-        convert_bar.enable_steady_tick(Duration::from_millis(100));
-        convert_bar.set_message("Converting entire video to MP3");
-
-        if let Some(ref dest_dir) = cli.dest {
-            fs::create_dir_all(dest_dir).context("Failed to create destination directory")?;
-        }
-
-        // Generate filename from video title, ignoring --prefix-name
-        let title_prefix = metadata
-            .get("title")
-            .and_then(|t| t.as_str())
-            .and_then(make_title_prefix)
-            .unwrap_or_else(|| "untitled".to_string());
-
-        let filename = if let Some(ref prefix) = cli.prefix {
-            format!("{}_{}.{}", prefix, title_prefix, cli.audio_format)
-        } else {
-            format!("{}.{}", title_prefix, cli.audio_format)
-        };
-
-        let out_path = match &cli.dest {
-            Some(dir) => dir.join(filename),
-            None => PathBuf::from(&filename),
-        };
-
-        // Convert entire file without splitting
-        run_command(Command::new("ffmpeg").args([
-            "-hide_banner",
-            "-loglevel",
-            "error",
-            "-y",
-            "-i",
-            &cli.output.to_string_lossy(),
-            "-c:a",
-            "libmp3lame",
-            "-b:a",
-            "192k",
-            &out_path.to_string_lossy(),
-        ]))
-        .context("ffmpeg failed to convert entire video")?;
-
-        convert_bar.finish_and_clear();
-    } else {
-        // Has chapters: split as before
-        let total = u64::try_from(chapters.len()).unwrap_or(u64::MAX);
-        let split_bar = mp.add(ProgressBar::new(total));
-        if let Ok(style) = ProgressStyle::with_template(
-            "{spinner:.green} \x1b[90m{elapsed_precise}\x1b[0m [{bar:40.cyan/blue}] {pos}/{len} {msg}",
-        ) {
-            split_bar.set_style(style.progress_chars("#>-"));
-        }
-        split_bar.set_message("Splitting audio");
-
-        if let Some(ref dest_dir) = cli.dest {
-            fs::create_dir_all(dest_dir).context("Failed to create destination directory")?;
-        }
-
-        let pad_width = compute_pad_width(cli.numbers, chapters.len());
+    }
 
-        for (index, ch) in chapters.iter().enumerate() {
-            let safe_title = sanitize(&ch.title).unwrap_or_else(|| format!("part-{}", index + 1));
-            let title_prefix = if cli.prefix_name {
-                metadata
-                    .get("title")
-                    .and_then(|t| t.as_str())
-                    .and_then(make_title_prefix)
-            } else {
-                None
-            };
-            let filename =
-                build_output_filename(cli, index, pad_width, &safe_title, title_prefix.as_deref());
-            let out_path = match &cli.dest {
-                Some(dir) => dir.join(filename),
-                None => PathBuf::from(&filename),
-            };
-
-            let start = ch.start_time.max(0.0);
-            let duration = (ch.end_time - ch.start_time).max(0.0);
-            if !duration.is_finite() || duration < 1.0 {
-                split_bar.println(format!(
-                    "\x1b[90mSkipping '{}' (<1s duration)\x1b[0m",
-                    ch.title
-                ));
-                split_bar.inc(1);
-                continue;
-            }
-            run_command(Command::new("ffmpeg").args([
-                "-hide_banner",
-                "-loglevel",
-                "error",
-                "-y",
-                "-ss",
-                &format!("{start:.3}"),
-                "-t",
-                &format!("{duration:.3}"),
-                "-i",
-                &cli.output.to_string_lossy(),
-                "-c",
-                "copy",
-                &out_path.to_string_lossy(),
-            ]))
-            .with_context(|| format!("ffmpeg failed to split '{}'", ch.title))?;
-
-            split_bar.inc(1);
+    fn dates(&self) -> DateRange {
+        DateRange {
+            after: self.date_after.clone(),
+            before: self.date_before.clone(),
         }
-        split_bar.finish_and_clear();
     }
 
-    if !cli.keep {
-        let _ = fs::remove_file(&cli.output);
+    fn apply(&self, opts: JobOptions) -> JobOptions {
+        JobOptions {
+            output: self.output.clone(),
+            work_dir: self.work_dir.clone(),
+            audio_formats: self.audio_formats.clone(),
+            native: self.native,
+            keep: self.keep,
+            embed_cover: self.embed_cover,
+            square_cover: self.square_cover,
+            cue: self.cue,
+            split: !self.no_split,
+            album_from_playlist: self.album_from_playlist,
+            dates: self.dates(),
+            naming: NamingOptions {
+                format_dirs: self.format_dirs,
+                ..opts.naming
+            },
+            ..opts
+        }
     }
-    Ok(())
 }
 
-fn ensure_binaries_present(auto_yes: bool) -> Result<()> {
-    let required = ["yt-dlp", "ffmpeg"];
-    let missing: Vec<&str> = required
-        .iter()
-        .copied()
-        .filter(|bin| which(bin).is_err())
-        .collect();
+fn main() -> Result<()> {
+    let cli = Cli::parse();
 
-    if missing.is_empty() {
+    if let Some(Commands::Split(args)) = &cli.command {
+        let report = exit_if_interrupted(run_split(args))?;
+        print_skipped(&report);
         return Ok(());
     }
 
-    let msg_list = missing.join(", ");
-    if !auto_yes {
-        eprint!("Missing binaries: {msg_list}. Install automatically? [y/N]: ");
-        io::stderr().flush().ok();
-
-        let mut answer = String::new();
-        io::stdin()
-            .read_line(&mut answer)
-            .context("Failed to read user input")?;
-        let ans = answer.trim().to_ascii_lowercase();
-        let yes = matches!(ans.as_str(), "y" | "yes" | "д" | "да");
-        if !yes {
-            bail!("Required: {msg_list}. Install manually or run with --yes for auto-install");
-        }
-    }
-
-    install_missing(&missing)?;
-
-    // Re-check
-    for bin in &missing {
-        which(bin).with_context(|| format!("Бинарник '{bin}' не найден после установки"))?;
-    }
-    Ok(())
-}
-
-#[allow(clippy::too_many_lines)]
-fn install_missing(missing: &[&str]) -> Result<()> {
-    let spinner = new_spinner("Installing dependencies");
-
-    #[allow(clippy::match_same_arms)]
-    let installer = match std::env::consts::OS {
-        "macos" => choose_first_available(&["brew"]),
-        "linux" => choose_first_available(&["apt-get", "dnf", "yum", "pacman", "zypper", "apk"]),
-        "windows" => choose_first_available(&["winget", "choco", "scoop"]),
-        _ => None,
-    };
-
-    let Some(installer) = installer else {
-        spinner.finish_with_message("Auto-install is unavailable");
-        bail!(
-            "Cannot determine package manager. Install manually: {}",
-            missing.join(", ")
-        );
+    cli.common.check_chapters()?;
+    let opts = JobOptions {
+        archive: match &cli.download.archive {
+            Some(path) => Some(Arc::new(Archive::open(path)?)),
+            None => None,
+        },
+        ..cli
+            .download
+            .apply(cli.common.job_options(ChapterOptions::default()))
     };
+    opts.check_encode()?;
+    ensure_binaries_present(DOWNLOAD_BINARIES, cli.common.yes)?;
 
-    // Run installers with minimal dependency footprint where possible
-    let result: Result<()> = if cfg!(target_os = "macos") && installer == "brew" {
-        // Disable auto-update and cleanup, install only requested formulae
-        let mut cmd = Command::new("brew");
-        cmd.env("HOMEBREW_NO_AUTO_UPDATE", "1")
-            .env("HOMEBREW_NO_INSTALL_CLEANUP", "1")
-            .env("HOMEBREW_NO_ANALYTICS", "1")
-            .args(["install", "--formula"])
-            .args(missing);
-        run_streaming_lines(&spinner, &mut cmd)
-    } else if cfg!(target_os = "linux") {
-        match installer {
-            "apt-get" => {
-                // Update index only, then install without recommends and without upgrading existing pkgs
-                run_streaming_lines(
-                    &spinner,
-                    Command::new("sudo").args(["-n", "apt-get", "update"]),
-                )?;
-                run_streaming_lines(
-                    &spinner,
-                    Command::new("sudo")
-                        .args([
-                            "-n",
-                            "apt-get",
-                            "install",
-                            "-y",
-                            "--no-install-recommends",
-                            "--no-upgrade",
-                        ])
-                        .args(missing),
-                )
-            }
-            "dnf" => run_streaming_lines(
-                &spinner,
-                Command::new("sudo")
-                    .args([
-                        "-n",
-                        "dnf",
-                        "install",
-                        "-y",
-                        "--setopt=install_weak_deps=False",
-                    ])
-                    .args(missing),
-            ),
-            "yum" => run_streaming_lines(
-                &spinner,
-                Command::new("sudo")
-                    .args(["-n", "yum", "install", "-y"])
-                    .args(missing),
-            ),
-            "pacman" => run_streaming_lines(
-                &spinner,
-                Command::new("sudo")
-                    .args(["-n", "pacman", "-S", "--noconfirm", "--needed"])
-                    .args(missing),
-            ),
-            "zypper" => run_streaming_lines(
-                &spinner,
-                Command::new("sudo")
-                    .args(["-n", "zypper", "install", "-y", "--no-recommends"])
-                    .args(missing),
-            ),
-            "apk" => run_streaming_lines(
-                &spinner,
-                Command::new("sudo")
-                    .args(["-n", "apk", "add", "--no-cache"])
-                    .args(missing),
-            ),
-            _ => Err(anyhow::anyhow!("unsupported installer")),
-        }
-    } else if cfg!(target_os = "windows") {
-        match installer {
-            // Map to more exact IDs when possible
-            "winget" => {
-                let mapped: Vec<String> = missing
-                    .iter()
-                    .map(|&p| match p {
-                        "ffmpeg" => "Gyan.FFmpeg".to_string(),
-                        "yt-dlp" => "yt-dlp.yt-dlp".to_string(),
-                        other => other.to_string(),
-                    })
-                    .collect();
-                run_streaming_lines(
-                    &spinner,
-                    Command::new("winget")
-                        .args([
-                            "install",
-                            "--silent",
-                            "--accept-package-agreements",
-                            "--accept-source-agreements",
-                            "--exact",
-                        ])
-                        .args(&mapped),
-                )
-            }
-            "choco" => run_streaming_lines(
-                &spinner,
-                Command::new("choco")
-                    .args(["install", "-y", "--no-progress"])
-                    .args(missing),
-            ),
-            "scoop" => run_streaming_lines(
-                &spinner,
-                Command::new("scoop").args(["install"]).args(missing),
-            ),
-            _ => Err(anyhow::anyhow!("unsupported installer")),
-        }
-    } else {
-        Err(anyhow::anyhow!("unsupported os"))
-    };
+    let mp = MultiProgress::new();
 
-    if result.is_ok() {
-        spinner.finish_with_message("Dependencies installed");
-        Ok(())
-    } else {
-        spinner.finish_with_message("Auto-install failed");
-        bail!(
-            "Failed to install: {}. Install manually via package manager",
-            missing.join(", ")
-        );
+    // Resolve inputs: URLs given directly, files with URLs and stdin
+    let listed = cli.inputs.len() > 1 || cli.inputs.iter().any(|i| is_list_input(i));
+    let inputs = read_inputs(&cli.inputs, &opts)?;
+    // Installed only now, so a Ctrl-C at the install prompt or while stdin is
+    // read still quits right away
+    install_handler()?;
+    let jobs = exit_if_interrupted(expand_jobs(
+        &mp,
+        &cli.download.playlist_options(),
+        &inputs,
+        cli.download.parallel,
+        listed,
+    ))?;
+
+    if let [job] = jobs.as_slice()
+        && !listed
+        && job.opts.playlist.is_none()
+    {
+        // single video
+        let report = exit_if_interrupted(download_and_split(
+            &ProgressGroup::new(&mp),
+            &job.opts,
+            &job.url,
+        ))?;
+        print_skipped(&report);
+        return Ok(());
     }
-}
-
-fn choose_first_available<'a>(candidates: &'a [&'a str]) -> Option<&'a str> {
-    candidates
-        .iter()
-        .find(|&&name| which(name).is_ok())
-        .copied()
-}
-
-fn fetch_metadata_json(url: &str) -> Result<Value> {
-    let output = Command::new("yt-dlp")
-        .args(["-J", url])
-        .output()
-        .context("Failed to execute yt-dlp for JSON metadata")?;
-
-    if !output.status.success() {
-        bail!("yt-dlp -J returned non-zero exit code");
+    if jobs.is_empty() {
+        bail!("No videos to process");
     }
 
-    let json = serde_json::from_slice(&output.stdout).context("Invalid JSON from yt-dlp")?;
-    Ok(json)
-}
+    // batch mode
+    let overall = add_counter(
+        &ProgressGroup::new(&mp),
+        u64::try_from(jobs.len()).unwrap_or(0),
+        "Processing URLs",
+    );
+    overall.set_position(0);
+    overall.enable_steady_tick(Duration::from_millis(100));
 
-fn extract_chapters(v: &Value) -> Result<Vec<Chapter>> {
-    let Some(chapters_val) = v.get("chapters") else {
-        bail!("No 'chapters' field in metadata");
-    };
-    let chapters: Vec<Chapter> =
-        serde_json::from_value(chapters_val.clone()).context("Failed to parse chapters")?;
-    Ok(chapters)
-}
-
-fn sanitize(title: &str) -> Option<String> {
-    let filtered: String = title
-        .chars()
-        .map(|ch| match ch {
-            'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '-' | ' ' => ch,
-            _ => ' ',
-        })
-        .collect::<String>()
-        .split_whitespace()
-        .collect::<Vec<_>>()
-        .join("_");
-
-    if filtered.is_empty() {
-        None
-    } else {
-        Some(filtered)
+    let report = run_batch(&mp, &cli.download.batch_options(), &jobs, &overall);
+    if is_interrupted() {
+        overall.abandon_with_message("Interrupted");
+        eprintln!("\x1b[33mInterrupted: {}\x1b[0m", report.summary());
+        process::exit(EXIT_INTERRUPTED);
     }
+    overall.finish_with_message(format!("All done: {}", report.summary()));
+    Ok(())
 }
 
-fn new_spinner(msg: &str) -> ProgressBar {
-    let pb = ProgressBar::new_spinner();
-    pb.enable_steady_tick(Duration::from_millis(100));
-    if let Ok(style) = ProgressStyle::with_template("{spinner:.green} {msg}") {
-        pb.set_style(style);
-    }
-    pb.set_message(msg.to_string());
-    pb
+/// Whether `input` names a list of URLs (`-` or a file) rather than one URL.
+fn is_list_input(input: &str) -> bool {
+    input == "-" || Path::new(input).is_file()
 }
 
-fn run_command(cmd: &mut Command) -> Result<()> {
-    let status = cmd.status().context("Failed to start process")?;
-    if status.success() {
-        Ok(())
-    } else {
-        bail!("Process exited with status: {status}")
-    }
+/// A job read from the command line or a list, with the `| key=value` options
+/// of its line.
+struct InputJob {
+    job: BatchJob,
+    options: Vec<(String, String)>,
 }
 
-// note: removed generic streaming helper in favor of yt-dlp specific progress handler
-
-fn run_streaming_lines(pb: &ProgressBar, cmd: &mut Command) -> Result<()> {
-    cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
-    let mut child = cmd.spawn().context("Failed to start process")?;
-
-    let stdout = child.stdout.take().context("Failed to capture stdout")?;
-    let stderr = child.stderr.take().context("Failed to capture stderr")?;
-
-    let pb_out = pb.clone();
-    let out_handle = thread::spawn(move || {
-        let reader = BufReader::new(stdout);
-        for line in reader.lines().map_while(Result::ok) {
-            pb_out.println(line);
-        }
-    });
-
-    let pb_err = pb.clone();
-    let err_handle = thread::spawn(move || {
-        let reader = BufReader::new(stderr);
-        for line in reader.lines().map_while(Result::ok) {
-            pb_err.println(line);
-        }
-    });
-
-    let status = child.wait().context("Failed to wait for process")?;
-    let _ = out_handle.join();
-    let _ = err_handle.join();
-    if status.success() {
-        Ok(())
-    } else {
-        bail!("Process exited with status: {status}")
-    }
-}
-
-fn run_ytdlp_with_progress(
-    pb: &ProgressBar,
-    logs: &[ProgressBar],
-    cmd: &mut Command,
-) -> Result<()> {
-    cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
-    let mut child = cmd.spawn().context("Failed to start yt-dlp")?;
-
-    let stdout = child.stdout.take().context("Failed to capture stdout")?;
-    let stderr = child.stderr.take().context("Failed to capture stderr")?;
-
-    // yt-dlp prints progress lines like:
-    // "[download]  81.6% of   59.10MiB at    3.47MiB/s ETA 00:01"
-    let logs_buffer = Arc::new(Mutex::new(VecDeque::with_capacity(5)));
-    let pb_out = pb.clone();
-    let logs_out_vec: Vec<ProgressBar> = logs.to_vec();
-    let logs_out_buf = Arc::clone(&logs_buffer);
-    let out_handle = thread::spawn(move || {
-        let reader = BufReader::new(stdout);
-        for line in reader.lines().map_while(Result::ok) {
-            if let Some((permille, speed, _eta)) = parse_ytdlp_progress(&line) {
-                pb_out.set_length(1000);
-                pb_out.set_position(permille.min(1000));
-                let percent_int = permille / 10;
-                let percent_frac = permille % 10;
-                let fixed_spd = speed
-                    .as_deref()
-                    .map_or_else(|| "          ".to_string(), |s| format!("{s:>10}"));
-                let right = format!(" | {fixed_spd}");
-                pb_out.set_message(format!(
-                    "{percent_int}.{percent_frac}%\x1b[90m{right}\x1b[0m"
-                ));
-            } else {
-                // hide non-progress logs during download
-                pb_out.set_message("Downloading audio");
-                if let Ok(mut dq) = logs_out_buf.lock() {
-                    if dq.len() == 5 {
-                        dq.pop_front();
-                    }
-                    dq.push_back(line);
-                    // update log bars with last lines
-                    let lines: Vec<String> = dq.iter().cloned().collect();
-                    let start = if lines.len() > 5 { lines.len() - 5 } else { 0 };
-                    let slice = &lines[start..];
-                    // clear all first
-                    for b in &logs_out_vec {
-                        b.set_message(String::new());
-                    }
-                    for (i, l) in slice.iter().enumerate() {
-                        logs_out_vec[i].set_message(format!("\x1b[90m{l}\x1b[0m"));
-                    }
-                }
-            }
-        }
-    });
-
-    let pb_err = pb.clone();
-    let logs_err_vec: Vec<ProgressBar> = logs.to_vec();
-    let logs_err_buf = Arc::clone(&logs_buffer);
-    let err_handle = thread::spawn(move || {
-        let reader = BufReader::new(stderr);
-        for line in reader.lines().map_while(Result::ok) {
-            if let Some((permille, speed, _eta)) = parse_ytdlp_progress(&line) {
-                pb_err.set_length(1000);
-                pb_err.set_position(permille.min(1000));
-                let percent_int = permille / 10;
-                let percent_frac = permille % 10;
-                let fixed_spd = speed
-                    .as_deref()
-                    .map_or_else(|| "          ".to_string(), |s| format!("{s:>10}"));
-                let right = format!(" | {fixed_spd}");
-                pb_err.set_message(format!(
-                    "{percent_int}.{percent_frac}%\x1b[90m{right}\x1b[0m"
-                ));
-            } else {
-                // hide during success path, but keep logs for potential error reporting
-                pb_err.set_message("Downloading audio");
-                if let Ok(mut dq) = logs_err_buf.lock() {
-                    if dq.len() == 5 {
-                        dq.pop_front();
-                    }
-                    dq.push_back(line);
-                    let lines: Vec<String> = dq.iter().cloned().collect();
-                    let start = if lines.len() > 5 { lines.len() - 5 } else { 0 };
-                    let slice = &lines[start..];
-                    for b in &logs_err_vec {
-                        b.set_message(String::new());
-                    }
-                    for (i, l) in slice.iter().enumerate() {
-                        logs_err_vec[i].set_message(format!("\x1b[90m{l}\x1b[0m"));
-                    }
-                }
+/// Collects the jobs of all positional inputs, in order, with `opts`.
+///
+/// Files and `-` (stdin, read once) contribute one input per line; an input
+/// given directly must be valid.
+fn read_inputs(inputs: &[String], opts: &JobOptions) -> Result<Vec<InputJob>> {
+    let mut jobs = Vec::new();
+    let mut read_stdin = false;
+    for input in inputs {
+        if input == "-" {
+            if !read_stdin {
+                read_stdin = true;
+                let content = io::read_to_string(io::stdin()).context("Failed to read stdin")?;
+                jobs.extend(parse_url_list(&content, "stdin", opts));
             }
+        } else if is_list_input(input) {
+            let content = fs::read_to_string(input)
+                .with_context(|| format!("Failed to read input file {input}"))?;
+            jobs.extend(parse_url_list(&content, input, opts));
+        } else if looks_like_path(input) {
+            bail!("No such file '{input}'");
+        } else {
+            jobs.push(InputJob {
+                job: BatchJob {
+                    url: input.parse::<Input>()?.url(),
+                    opts: opts.clone(),
+                },
+                options: Vec::new(),
+            });
         }
-        // no logs collected currently
-        Vec::<String>::new()
-    });
-
-    let status = child.wait().context("Failed to wait for yt-dlp")?;
-    let _ = out_handle.join();
-    let err_logs = err_handle.join().unwrap_or_default();
-    if status.success() {
-        Ok(())
-    } else {
-        for line in err_logs {
-            eprintln!("{line}");
-        }
-        bail!("yt-dlp exited with status: {status}")
     }
+    Ok(jobs)
 }
 
-fn parse_ytdlp_progress(line: &str) -> Option<(u64, Option<String>, Option<String>)> {
-    if !line.starts_with("[download]") || !line.contains('%') {
-        return None;
+/// The jobs listed in `content`, one per line, with the line's options
+/// applied over `opts`. Reports the lines of `source` that aren't URLs, video
+/// ids or searches, or have invalid options, and why.
+fn parse_url_list(content: &str, source: &str, opts: &JobOptions) -> Vec<InputJob> {
+    let lines: Vec<(usize, &str)> = (1..)
+        .zip(content.lines().map(str::trim))
+        .filter(|(_, l)| !l.is_empty() && !l.starts_with('#'))
+        .collect();
+    if lines.is_empty() {
+        eprintln!("\x1b[33m{source} contains no URLs\x1b[0m");
     }
-    // Percent with one decimal
-    let percent_part = line.split('%').next()?;
-    let token = percent_part.split_whitespace().last()?;
-    let mut it = token.split('.');
-    let whole = it.next()?;
-    let whole_num: u64 = whole.parse().ok()?;
-    let frac_digit: u64 = it
-        .next()
-        .and_then(|s| s.chars().next())
-        .and_then(|c| c.to_digit(10))
-        .map_or(0, u64::from);
-    let permille = whole_num
-        .saturating_mul(10)
-        .saturating_add(frac_digit)
-        .min(1000);
-
-    // Speed and ETA
-    let mut pieces = line.split_whitespace();
-    let mut speed: Option<String> = None;
-    let mut eta: Option<String> = None;
-    while let Some(word) = pieces.next() {
-        if word == "at" {
-            if let Some(val) = pieces.next() {
-                if val == "Unknown" {
-                    // skip unit after Unknown if present
-                    let _ = pieces.next();
-                } else {
-                    let unit = pieces.next().unwrap_or("");
-                    // some yt-dlp lines include trailing 'ETA' in the token stream; cut speed only
-                    speed = Some(format!("{val} {unit}").replace(" ETA", ""));
-                }
-            }
-        }
-        if word == "ETA" {
-            if let Some(val) = pieces.next() {
-                if val != "Unknown" {
-                    eta = Some(val.to_string());
-                }
-            }
+
+    // Log invalid lines in red
+    let mut jobs = Vec::new();
+    for (n, line) in lines {
+        match line_job(line, opts) {
+            Ok(job) => jobs.push(job),
+            Err(err) => eprintln!("\x1b[31mSkipping {source}:{n}: {err}\x1b[0m"),
         }
     }
-
-    Some((permille, speed, eta))
+    jobs
 }
 
-fn compute_pad_width(use_numbers: bool, count: usize) -> usize {
-    if !use_numbers {
-        return 0;
-    }
-    match count {
-        0..=9 => 1,
-        10..=99 => 2,
-        100..=999 => 3,
-        _ => 4,
-    }
+fn line_job(line: &str, opts: &JobOptions) -> Result<InputJob> {
+    let BatchLine { input, options } = line.parse()?;
+    let mut opts = opts.clone();
+    for (key, value) in &options {
+        opts.set_option(key, value)?;
+    }
+    opts.check_encode()?;
+    Ok(InputJob {
+        job: BatchJob {
+            url: input.url(),
+            opts,
+        },
+        options,
+    })
 }
 
-fn make_title_prefix(title: &str) -> Option<String> {
-    // Remove tags in brackets at the beginning (like [FREE], [HD], etc.)
-    let mut cleaned = title;
-    loop {
-        let trimmed = cleaned.trim_start();
-        if let Some(start) = trimmed.find('[') {
-            if start == 0 {
-                if let Some(end) = trimmed.find(']') {
-                    cleaned = trimmed[end + 1..].trim_start();
-                    continue;
-                }
+/// Expands playlist and channel URLs into one job per video, dropping videos
+/// already listed by an earlier URL. A dropped video whose line had other
+/// options than the kept one is reported.
+///
+/// URLs are listed `parallel` at a time. In batch mode a URL that can't be
+/// listed is reported and skipped instead of failing the run.
+fn expand_jobs(
+    mp: &MultiProgress,
+    playlist: &PlaylistOptions,
+    inputs: &[InputJob],
+    parallel: usize,
+    batch: bool,
+) -> Result<Vec<BatchJob>> {
+    let spinner = add_spinner(&ProgressGroup::new(mp), "Listing playlists");
+    let urls: Vec<&str> = inputs.iter().map(|input| input.job.url.as_str()).collect();
+    let listings = expand_urls(&urls, playlist, parallel);
+    let mut jobs = Vec::new();
+    let mut seen: HashMap<String, &[(String, String)]> = HashMap::new();
+    let mut duplicates = 0;
+    for (
+        InputJob {
+            job: BatchJob { url, opts },
+            options,
+        },
+        listing,
+    ) in inputs.iter().zip(listings)
+    {
+        let entries = match listing {
+            Ok(entries) => entries,
+            Err(err) if batch && !is_interrupted() => {
+                spinner.println(format!("\x1b[31m{url}: {err:#}\x1b[0m"));
+                continue;
+            }
+            Err(err) => {
+                spinner.finish_and_clear();
+                return Err(err);
             }
+        };
+        if let Some(position) = entries.first().and_then(|e| e.position.as_ref()) {
+            spinner.println(format!(
+                "\x1b[90m{url}: {} of {} videos from '{}'\x1b[0m",
+                entries.len(),
+                position.count,
+                position.title.as_deref().unwrap_or("playlist")
+            ));
         }
-        if let Some(start) = trimmed.find('(') {
-            if start == 0 {
-                if let Some(end) = trimmed.find(')') {
-                    cleaned = trimmed[end + 1..].trim_start();
-                    continue;
+        for entry in entries {
+            let key = video_key(&entry.url);
+            if let Some(kept) = seen.get(&key) {
+                if kept != options {
+                    spinner.println(format!(
+                        "\x1b[33m{}: listed again with other options; keeping the first\x1b[0m",
+                        entry.url
+                    ));
                 }
+                duplicates += 1;
+                continue;
             }
+            seen.insert(key, options);
+            jobs.push(BatchJob {
+                url: entry.url,
+                opts: JobOptions {
+                    playlist: entry.position,
+                    ..opts.clone()
+                },
+            });
         }
-        break;
     }
-
-    // Cut at content separators like "|" or "(" (but not at beginning)
-    let mut cut_pos = cleaned.len();
-    if let Some(p) = cleaned.find(" | ") {
-        cut_pos = cut_pos.min(p);
+    if duplicates > 0 {
+        spinner.println(format!(
+            "\x1b[90mSkipping {duplicates} duplicate videos\x1b[0m"
+        ));
     }
-    if let Some(p) = cleaned.find('|') {
-        cut_pos = cut_pos.min(p);
-    }
-    // Cut at brackets or parentheses that are not at the beginning
-    if let Some(p) = cleaned.find('(') {
-        if p > 0 {
-            cut_pos = cut_pos.min(p);
-        }
-    }
-    if let Some(p) = cleaned.find('[') {
-        if p > 0 {
-            cut_pos = cut_pos.min(p);
-        }
-    }
-
-    let slice = &cleaned[..cut_pos].trim();
-    let lowered = slice.to_lowercase();
-    let sanitized = sanitize(&lowered)?;
-    let mut chars = sanitized.chars().take(60).collect::<String>();
+    spinner.finish_and_clear();
+    Ok(jobs)
+}
 
-    // Clean up trailing underscores
-    while chars.ends_with('_') {
-        chars.pop();
+/// Parses a non-negative number of seconds such as `1` or `0.5`.
+fn parse_delay(s: &str) -> Result<Duration> {
+    let secs: f64 = s
+        .parse()
+        .with_context(|| format!("Invalid delay '{s}'; expected seconds such as 1 or 0.5"))?;
+    if secs < 0.0 {
+        bail!("Invalid delay '{s}'; it can't be negative");
     }
-
-    if chars.is_empty() { None } else { Some(chars) }
+    Duration::try_from_secs_f64(secs)
+        .with_context(|| format!("Invalid delay '{s}'; too large or not finite"))
 }
 
-fn build_output_filename(
-    cli: &Cli,
-    index: usize,
-    pad_width: usize,
-    safe_title: &str,
-    title_prefix: Option<&str>,
-) -> String {
-    let mut parts: Vec<String> = Vec::new();
-    if let Some(pfx) = &cli.prefix {
-        if !pfx.is_empty() {
-            parts.push(pfx.clone());
-        }
-    }
-    if let Some(tp) = title_prefix {
-        if !tp.is_empty() {
-            parts.push(tp.to_string());
-        }
-    }
-    if cli.numbers && pad_width > 0 {
-        parts.push(format!("{:0width$}", index + 1, width = pad_width));
+/// Turns a failure caused by Ctrl-C into a short note and exit code 130.
+fn exit_if_interrupted<T>(result: Result<T>) -> Result<T> {
+    if let Err(err) = &result
+        && is_interrupted()
+    {
+        eprintln!("\x1b[33m{err:#}\x1b[0m");
+        process::exit(EXIT_INTERRUPTED);
     }
-    parts.push(safe_title.to_string());
-    let name = parts.join("_");
-    format!("{}.{}", name, cli.audio_format)
+    result
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use rstest::rstest;
-
-    #[rstest]
-    #[case(
-        "[FREE] Phonk x Tyga Type Beat - Salt Shaker | Club Banger Instrumental | Club Type Beat 2024",
-        "phonk_x_tyga_type_beat_-_salt_shaker"
-    )]
-    #[case("Simple Song Name", "simple_song_name")]
-    #[case("[HD] (Official) Artist Name - Song Title", "artist_name_-_song_title")]
-    #[case("(2024) [EXCLUSIVE] Beat Name | Type Beat", "beat_name")]
-    #[case("No Tags Here", "no_tags_here")]
-    #[case("[FREE] Beat - Track Name | Producer 2024", "beat_-_track_name")]
-    #[case("Artist - Song Title (Remix)", "artist_-_song_title")]
-    #[case("Song Title [Official Video]", "song_title")]
-    #[case("Track Name (Official Audio)", "track_name")]
-    #[case("Music Title - Feat. Artist (2024)", "music_title_-_feat_artist")]
-    fn test_make_title_prefix(#[case] input: &str, #[case] expected: &str) {
-        let result = make_title_prefix(input);
-        assert_eq!(result, Some(expected.to_string()));
+/// Notes how many existing tracks `--overwrite skip` left alone.
+fn print_skipped(report: &SplitReport) {
+    if !report.skipped.is_empty() {
+        eprintln!(
+            "\x1b[90m{} existing tracks skipped\x1b[0m",
+            report.skipped.len()
+        );
     }
+}
 
-    #[rstest]
-    #[case("")]
-    #[case("[")]
-    #[case("()")]
-    #[case("!@#$%^&*")]
-    fn test_make_title_prefix_invalid(#[case] input: &str) {
-        let result = make_title_prefix(input);
-        assert_eq!(result, None);
-    }
+fn run_split(args: &SplitArgs) -> Result<SplitReport> {
+    ensure_binaries_present(SPLIT_BINARIES, args.common.yes)?;
+    args.common.check_chapters()?;
+    install_handler()?;
+
+    let opts = args.common.job_options(ChapterOptions {
+        sources: vec![ChapterSource::Embedded],
+    });
+    split_file(
+        &ProgressGroup::new(&MultiProgress::new()),
+        &opts,
+        &args.file,
+    )
 }
@@ -0,0 +1,104 @@
+//! Typed view of the `yt-dlp -J` fields slycer uses.
+
+use serde::{Deserialize, Deserializer, de::DeserializeOwned};
+use serde_json::Value;
+
+/// Video fields used for naming and tagging. Missing or mistyped fields are
+/// `None`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct VideoInfo {
+    #[serde(deserialize_with = "lenient")]
+    pub id: Option<String>,
+    #[serde(deserialize_with = "lenient")]
+    pub title: Option<String>,
+    #[serde(deserialize_with = "lenient")]
+    pub uploader: Option<String>,
+    /// Set by extractors that know the performing artist (e.g. YouTube Music)
+    #[serde(deserialize_with = "lenient")]
+    pub artist: Option<String>,
+    /// `YYYYMMDD`, as reported by yt-dlp
+    #[serde(deserialize_with = "lenient")]
+    pub upload_date: Option<String>,
+    #[serde(deserialize_with = "lenient")]
+    pub playlist_index: Option<u64>,
+    #[serde(deserialize_with = "lenient")]
+    pub playlist_title: Option<String>,
+    #[serde(deserialize_with = "lenient")]
+    pub playlist_count: Option<u64>,
+    #[serde(deserialize_with = "lenient")]
+    pub playlist_uploader: Option<String>,
+    #[serde(deserialize_with = "lenient")]
+    pub webpage_url: Option<String>,
+    #[serde(deserialize_with = "lenient")]
+    pub thumbnail: Option<String>,
+    #[serde(deserialize_with = "lenient")]
+    pub thumbnails: Vec<Thumbnail>,
+}
+
+/// One entry of the `thumbnails` list.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Thumbnail {
+    #[serde(deserialize_with = "lenient")]
+    pub url: String,
+    #[serde(deserialize_with = "lenient")]
+    pub width: Option<u64>,
+    #[serde(deserialize_with = "lenient")]
+    pub height: Option<u64>,
+    #[serde(deserialize_with = "lenient")]
+    pub preference: Option<i64>,
+}
+
+impl VideoInfo {
+    /// Extracts the known fields from raw metadata, ignoring malformed ones.
+    #[must_use]
+    pub fn from_metadata(v: &Value) -> Self {
+        Self::deserialize(v).unwrap_or_default()
+    }
+
+    /// One-based playlist index and playlist length, when both are known.
+    #[must_use]
+    pub fn playlist_position(&self) -> Option<(usize, usize)> {
+        let index = usize::try_from(self.playlist_index?).ok()?;
+        let count = usize::try_from(self.playlist_count?).ok()?;
+        (index > 0).then_some((index, count))
+    }
+}
+
+/// Deserializes a field, falling back to its default when it has another type
+/// than expected, so one odd field doesn't drop the others.
+fn lenient<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned + Default,
+{
+    let value = Value::deserialize(deserializer)?;
+    Ok(T::deserialize(value).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_from_metadata_skips_mistyped_fields() {
+        let info = VideoInfo::from_metadata(&json!({
+            "id": "abc",
+            "title": "Live Set",
+            "uploader": null,
+            "playlist_index": "3",
+            "playlist_count": 12,
+            "thumbnails": [{"url": "t.jpg", "width": "wide", "height": 720}],
+        }));
+        assert_eq!(info.id.as_deref(), Some("abc"));
+        assert_eq!(info.title.as_deref(), Some("Live Set"));
+        assert_eq!(info.uploader, None);
+        assert_eq!(info.playlist_index, None);
+        assert_eq!(info.playlist_count, Some(12));
+        assert_eq!(info.thumbnails[0].url, "t.jpg");
+        assert_eq!(info.thumbnails[0].width, None);
+        assert_eq!(info.thumbnails[0].height, Some(720));
+    }
+}
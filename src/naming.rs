@@ -0,0 +1,397 @@
+//! Output filename generation for split tracks.
+
+use crate::{metadata::VideoInfo, template::Template};
+
+/// Options controlling how output filenames are built.
+#[derive(Debug, Clone, Default)]
+pub struct NamingOptions {
+    /// Prefix for output track filenames
+    pub prefix: Option<String>,
+    /// Prepend zero-padded track numbers to filenames
+    pub numbers: bool,
+    /// Use video title (processed) as prefix
+    pub prefix_name: bool,
+    /// Filename template; replaces the prefix/number/title scheme when set
+    pub template: Option<Template>,
+    /// Put tracks in a subdirectory named after their format (`mp3/`, `opus/`)
+    pub format_dirs: bool,
+}
+
+/// Builds filenames for chapter tracks and whole-video conversions.
+#[derive(Debug, Clone)]
+pub struct Namer {
+    opts: NamingOptions,
+    ext: String,
+}
+
+impl Namer {
+    /// Namer producing files with extension `ext`.
+    #[must_use]
+    pub fn new(opts: NamingOptions, ext: impl Into<String>) -> Self {
+        Self {
+            opts,
+            ext: ext.into(),
+        }
+    }
+
+    #[must_use]
+    pub fn options(&self) -> &NamingOptions {
+        &self.opts
+    }
+
+    /// Filename for chapter `index` (zero-based) out of `total` chapters.
+    ///
+    /// May contain `/` when a template or `format_dirs` creates subdirectories.
+    #[must_use]
+    pub fn chapter_filename(
+        &self,
+        index: usize,
+        total: usize,
+        chapter_title: &str,
+        info: &VideoInfo,
+    ) -> String {
+        self.in_format_dir(self.chapter_name(index, total, chapter_title, info))
+    }
+
+    /// Filename for a video converted without splitting.
+    ///
+    /// Generated from the video title, ignoring `prefix_name`. A template is
+    /// rendered as a single track whose chapter is the video title.
+    #[must_use]
+    pub fn single_filename(&self, info: &VideoInfo) -> String {
+        self.in_format_dir(self.single_name(info))
+    }
+
+    /// Filename for a video converted as one track of an album made from its
+    /// playlist.
+    ///
+    /// Named like a chapter titled after the video, always numbered by
+    /// playlist position; templates see the position as `{n}` and `{total}`.
+    /// Falls back to [`Namer::single_filename`] when the position is unknown.
+    #[must_use]
+    pub fn playlist_track_filename(&self, info: &VideoInfo) -> String {
+        let Some((number, total)) = info.playlist_position() else {
+            return self.single_filename(info);
+        };
+        let numbered = Self::new(
+            NamingOptions {
+                numbers: true,
+                ..self.opts.clone()
+            },
+            self.ext.clone(),
+        );
+        let title = info.title.as_deref().unwrap_or("untitled");
+        self.in_format_dir(numbered.chapter_name(number - 1, total, title, info))
+    }
+
+    fn chapter_name(
+        &self,
+        index: usize,
+        total: usize,
+        chapter_title: &str,
+        info: &VideoInfo,
+    ) -> String {
+        if let Some(template) = &self.opts.template {
+            return self.render(template, index + 1, total, chapter_title, info);
+        }
+        let safe_title = sanitize(chapter_title).unwrap_or_else(|| format!("part-{}", index + 1));
+        let title_prefix = if self.opts.prefix_name {
+            info.title.as_deref().and_then(make_title_prefix)
+        } else {
+            None
+        };
+        let pad_width = compute_pad_width(self.opts.numbers, total);
+        build_output_filename(
+            &self.opts,
+            &self.ext,
+            index,
+            pad_width,
+            &safe_title,
+            title_prefix.as_deref(),
+        )
+    }
+
+    fn single_name(&self, info: &VideoInfo) -> String {
+        if let Some(template) = &self.opts.template {
+            let title = info.title.as_deref().unwrap_or("untitled");
+            return self.render(template, 1, 1, title, info);
+        }
+        let title_prefix = info
+            .title
+            .as_deref()
+            .and_then(make_title_prefix)
+            .unwrap_or_else(|| "untitled".to_string());
+
+        if let Some(ref prefix) = self.opts.prefix {
+            format!("{}_{}.{}", prefix, title_prefix, self.ext)
+        } else {
+            format!("{}.{}", title_prefix, self.ext)
+        }
+    }
+
+    fn in_format_dir(&self, name: String) -> String {
+        if self.opts.format_dirs {
+            format!("{}/{name}", self.ext)
+        } else {
+            name
+        }
+    }
+
+    fn render(
+        &self,
+        template: &Template,
+        number: usize,
+        total: usize,
+        chapter_title: &str,
+        info: &VideoInfo,
+    ) -> String {
+        let name = template.render(|field| match field {
+            "n" => Some(number.to_string()),
+            "total" => Some(total.to_string()),
+            "chapter" => Some(chapter_title.to_string()),
+            "video_title" => info.title.clone(),
+            "title_prefix" => info.title.as_deref().and_then(make_title_prefix),
+            "uploader" => info.uploader.clone(),
+            "artist" => info.artist.clone().or_else(|| info.uploader.clone()),
+            "upload_date" => info.upload_date.clone(),
+            "id" => info.id.clone(),
+            "playlist_index" => info.playlist_index.map(|i| i.to_string()),
+            "playlist_title" => info.playlist_title.clone(),
+            "ext" => Some(self.ext.clone()),
+            _ => None,
+        });
+        if template.has_ext() {
+            name
+        } else {
+            format!("{name}.{}", self.ext)
+        }
+    }
+}
+
+#[must_use]
+pub fn sanitize(title: &str) -> Option<String> {
+    let filtered: String = title
+        .chars()
+        .map(|ch| match ch {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '-' | ' ' => ch,
+            _ => ' ',
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("_");
+
+    if filtered.is_empty() {
+        None
+    } else {
+        Some(filtered)
+    }
+}
+
+#[must_use]
+pub fn compute_pad_width(use_numbers: bool, count: usize) -> usize {
+    if !use_numbers {
+        return 0;
+    }
+    match count {
+        0..=9 => 1,
+        10..=99 => 2,
+        100..=999 => 3,
+        _ => 4,
+    }
+}
+
+#[must_use]
+pub fn make_title_prefix(title: &str) -> Option<String> {
+    // Remove tags in brackets at the beginning (like [FREE], [HD], etc.)
+    let mut cleaned = title;
+    loop {
+        let trimmed = cleaned.trim_start();
+        if trimmed.starts_with('[')
+            && let Some(end) = trimmed.find(']')
+        {
+            cleaned = trimmed[end + 1..].trim_start();
+            continue;
+        }
+        if trimmed.starts_with('(')
+            && let Some(end) = trimmed.find(')')
+        {
+            cleaned = trimmed[end + 1..].trim_start();
+            continue;
+        }
+        break;
+    }
+
+    // Cut at content separators like "|" or "(" (but not at beginning)
+    let mut cut_pos = cleaned.len();
+    if let Some(p) = cleaned.find(" | ") {
+        cut_pos = cut_pos.min(p);
+    }
+    if let Some(p) = cleaned.find('|') {
+        cut_pos = cut_pos.min(p);
+    }
+    // Cut at brackets or parentheses that are not at the beginning
+    if let Some(p) = cleaned.find('(')
+        && p > 0
+    {
+        cut_pos = cut_pos.min(p);
+    }
+    if let Some(p) = cleaned.find('[')
+        && p > 0
+    {
+        cut_pos = cut_pos.min(p);
+    }
+
+    let slice = &cleaned[..cut_pos].trim();
+    let lowered = slice.to_lowercase();
+    let sanitized = sanitize(&lowered)?;
+    let mut chars = sanitized.chars().take(60).collect::<String>();
+
+    // Clean up trailing underscores
+    while chars.ends_with('_') {
+        chars.pop();
+    }
+
+    if chars.is_empty() { None } else { Some(chars) }
+}
+
+#[must_use]
+pub fn build_output_filename(
+    opts: &NamingOptions,
+    ext: &str,
+    index: usize,
+    pad_width: usize,
+    safe_title: &str,
+    title_prefix: Option<&str>,
+) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    if let Some(pfx) = &opts.prefix
+        && !pfx.is_empty()
+    {
+        parts.push(pfx.clone());
+    }
+    if let Some(tp) = title_prefix
+        && !tp.is_empty()
+    {
+        parts.push(tp.to_string());
+    }
+    if opts.numbers && pad_width > 0 {
+        parts.push(format!("{:0width$}", index + 1, width = pad_width));
+    }
+    parts.push(safe_title.to_string());
+    let name = parts.join("_");
+    format!("{name}.{ext}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(
+        "[FREE] Phonk x Tyga Type Beat - Salt Shaker | Club Banger Instrumental | Club Type Beat 2024",
+        "phonk_x_tyga_type_beat_-_salt_shaker"
+    )]
+    #[case("Simple Song Name", "simple_song_name")]
+    #[case("[HD] (Official) Artist Name - Song Title", "artist_name_-_song_title")]
+    #[case("(2024) [EXCLUSIVE] Beat Name | Type Beat", "beat_name")]
+    #[case("No Tags Here", "no_tags_here")]
+    #[case("[FREE] Beat - Track Name | Producer 2024", "beat_-_track_name")]
+    #[case("Artist - Song Title (Remix)", "artist_-_song_title")]
+    #[case("Song Title [Official Video]", "song_title")]
+    #[case("Track Name (Official Audio)", "track_name")]
+    #[case("Music Title - Feat. Artist (2024)", "music_title_-_feat_artist")]
+    fn test_make_title_prefix(#[case] input: &str, #[case] expected: &str) {
+        let result = make_title_prefix(input);
+        assert_eq!(result, Some(expected.to_string()));
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("[")]
+    #[case("()")]
+    #[case("!@#$%^&*")]
+    fn test_make_title_prefix_invalid(#[case] input: &str) {
+        let result = make_title_prefix(input);
+        assert_eq!(result, None);
+    }
+
+    #[rstest]
+    #[case(NamingOptions::default(), "Intro", "Intro.mp3")]
+    #[case(
+        NamingOptions { prefix: Some("wow".into()), ..NamingOptions::default() },
+        "Intro",
+        "wow_Intro.mp3"
+    )]
+    #[case(
+        NamingOptions { numbers: true, ..NamingOptions::default() },
+        "Intro",
+        "03_Intro.mp3"
+    )]
+    #[case(
+        NamingOptions { prefix_name: true, numbers: true, ..NamingOptions::default() },
+        "Intro",
+        "artist_-_album_03_Intro.mp3"
+    )]
+    #[case(NamingOptions::default(), "???", "part-3.mp3")]
+    #[case(
+        NamingOptions { format_dirs: true, numbers: true, ..NamingOptions::default() },
+        "Intro",
+        "mp3/03_Intro.mp3"
+    )]
+    fn test_chapter_filename(
+        #[case] opts: NamingOptions,
+        #[case] chapter: &str,
+        #[case] expected: &str,
+    ) {
+        let namer = Namer::new(opts, "mp3");
+        let info = VideoInfo {
+            title: Some("Artist - Album [Full]".into()),
+            ..VideoInfo::default()
+        };
+        let result = namer.chapter_filename(2, 12, chapter, &info);
+        assert_eq!(result, expected);
+    }
+
+    #[rstest]
+    #[case(NamingOptions::default(), "04_Song.mp3")]
+    #[case(
+        NamingOptions { template: Some("{playlist_title}/{n:02} {chapter}".parse().unwrap()), ..NamingOptions::default() },
+        "Album/04 Song.mp3"
+    )]
+    fn test_playlist_track_filename(#[case] opts: NamingOptions, #[case] expected: &str) {
+        let namer = Namer::new(opts, "mp3");
+        let info = VideoInfo {
+            title: Some("Song".into()),
+            playlist_index: Some(4),
+            playlist_count: Some(11),
+            playlist_title: Some("Album".into()),
+            ..VideoInfo::default()
+        };
+        assert_eq!(namer.playlist_track_filename(&info), expected);
+    }
+
+    #[rstest]
+    #[case("{n:02} - {artist} - {chapter}.{ext}", "03 - Someone - Intro.mp3")]
+    #[case("{ext}/{n:02} {chapter}", "mp3/03 Intro.mp3")]
+    #[case(
+        "{uploader}/{title_prefix}/{n} {chapter}",
+        "Someone/artist_-_album/3 Intro.mp3"
+    )]
+    fn test_chapter_filename_template(#[case] template: &str, #[case] expected: &str) {
+        let namer = Namer::new(
+            NamingOptions {
+                template: Some(template.parse().unwrap()),
+                ..NamingOptions::default()
+            },
+            "mp3",
+        );
+        let info = VideoInfo {
+            title: Some("Artist - Album [Full]".into()),
+            uploader: Some("Someone".into()),
+            ..VideoInfo::default()
+        };
+        assert_eq!(namer.chapter_filename(2, 12, "Intro", &info), expected);
+    }
+}
@@ -0,0 +1,407 @@
+//! Expanding playlist, channel and album URLs into one job per video.
+//!
+//! Expansion uses `yt-dlp --flat-playlist -J`, which lists the entries
+//! without resolving each video, so even long channels expand quickly.
+
+use std::{
+    fmt,
+    process::Command,
+    str::FromStr,
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+};
+
+use anyhow::{Context, Result, anyhow, bail};
+use serde_json::Value;
+
+use crate::{batch::host_of, interrupt::is_interrupted, process::run_output};
+
+/// Which entries of a playlist become jobs.
+#[derive(Debug, Clone, Default)]
+pub struct PlaylistOptions {
+    /// Playlist positions to keep (default: all)
+    pub items: Option<PlaylistItems>,
+    /// Keep at most this many entries, after the other filters. Entries
+    /// without a date count even if the date check made once their metadata
+    /// is fetched drops them.
+    pub max_items: Option<usize>,
+    /// Upload dates to keep; entries without a date are checked once their
+    /// metadata is fetched
+    pub dates: DateRange,
+}
+
+/// A set of 1-based playlist positions such as `1-3,7,10-`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlaylistItems {
+    /// Inclusive ranges; `None` as end means "to the last entry"
+    ranges: Vec<(u64, Option<u64>)>,
+}
+
+impl PlaylistItems {
+    /// Whether position `index` (1-based) is selected.
+    #[must_use]
+    pub fn contains(&self, index: u64) -> bool {
+        self.ranges
+            .iter()
+            .any(|&(start, end)| index >= start && end.is_none_or(|end| index <= end))
+    }
+}
+
+impl FromStr for PlaylistItems {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let parse = |n: &str| -> Result<u64> {
+            match n.trim().parse() {
+                Ok(0) | Err(_) => bail!("Invalid playlist item '{n}' in '{s}'; items start at 1"),
+                Ok(n) => Ok(n),
+            }
+        };
+        let mut ranges = Vec::new();
+        for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let range = match part.split_once('-') {
+                Some((start, "")) => (parse(start)?, None),
+                Some((start, end)) => (parse(start)?, Some(parse(end)?)),
+                None => {
+                    let n = parse(part)?;
+                    (n, Some(n))
+                }
+            };
+            ranges.push(range);
+        }
+        if ranges.is_empty() {
+            bail!("No playlist items in '{s}'");
+        }
+        Ok(Self { ranges })
+    }
+}
+
+impl fmt::Display for PlaylistItems {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = self
+            .ranges
+            .iter()
+            .map(|&(start, end)| match end {
+                Some(end) if end == start => start.to_string(),
+                Some(end) => format!("{start}-{end}"),
+                None => format!("{start}-"),
+            })
+            .collect();
+        f.write_str(&parts.join(","))
+    }
+}
+
+/// Inclusive range of upload dates, each `YYYYMMDD` like yt-dlp's `upload_date`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DateRange {
+    pub after: Option<String>,
+    pub before: Option<String>,
+}
+
+impl DateRange {
+    /// Whether `date` (`YYYYMMDD`) lies in the range.
+    #[must_use]
+    pub fn contains(&self, date: &str) -> bool {
+        self.after.as_deref().is_none_or(|after| date >= after)
+            && self.before.as_deref().is_none_or(|before| date <= before)
+    }
+}
+
+/// Parses `YYYYMMDD` or `YYYY-MM-DD` into `YYYYMMDD`.
+///
+/// # Errors
+/// Fails on anything else.
+pub fn parse_date(s: &str) -> Result<String> {
+    let digits: String = s.chars().filter(|c| *c != '-').collect();
+    let valid = digits.len() == 8
+        && digits.chars().all(|c| c.is_ascii_digit())
+        && (s.len() == 8 || (s.len() == 10 && &s[4..5] == "-" && &s[7..8] == "-"));
+    if !valid {
+        bail!("Invalid date '{s}'; expected YYYYMMDD or YYYY-MM-DD");
+    }
+    Ok(digits)
+}
+
+/// Where a video sits in the playlist it was expanded from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlaylistPosition {
+    /// 1-based position in the full playlist
+    pub index: u64,
+    /// Number of entries in the full playlist
+    pub count: u64,
+    pub id: Option<String>,
+    pub title: Option<String>,
+    pub uploader: Option<String>,
+}
+
+/// A video to process, possibly from a playlist.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlaylistEntry {
+    pub url: String,
+    /// `None` for a URL that was a single video
+    pub position: Option<PlaylistPosition>,
+}
+
+/// Expands `url` into its videos; a single video URL expands to itself.
+///
+/// # Errors
+/// Fails when `yt-dlp` fails or prints invalid JSON.
+pub fn expand_url(url: &str, opts: &PlaylistOptions) -> Result<Vec<PlaylistEntry>> {
+    if is_video_url(url) {
+        return Ok(vec![single(url)]);
+    }
+    let output = run_output(
+        Command::new("yt-dlp")
+            .args(["--flat-playlist", "--no-playlist", "-J"])
+            .arg(listing_url(url)),
+    )
+    .context("Failed to execute yt-dlp to list the playlist")?;
+    if !output.status.success() {
+        bail!("yt-dlp --flat-playlist returned non-zero exit code");
+    }
+    let json: Value = serde_json::from_slice(&output.stdout).context("Invalid JSON from yt-dlp")?;
+    if json.get("_type").and_then(Value::as_str) == Some("playlist") {
+        Ok(playlist_entries(&json, opts))
+    } else {
+        Ok(vec![single(url)])
+    }
+}
+
+/// Expands each of `urls` like [`expand_url`], listing up to `parallel` of
+/// them at once. Results are in the order of `urls`; URLs not reached
+/// because of an interrupt fail with "Interrupted".
+#[must_use]
+pub fn expand_urls(
+    urls: &[&str],
+    opts: &PlaylistOptions,
+    parallel: usize,
+) -> Vec<Result<Vec<PlaylistEntry>>> {
+    let next = AtomicUsize::new(0);
+    let results: Vec<Mutex<Option<Result<Vec<PlaylistEntry>>>>> =
+        urls.iter().map(|_| Mutex::new(None)).collect();
+    thread::scope(|scope| {
+        for _ in 0..parallel.max(1).min(urls.len()) {
+            scope.spawn(|| {
+                while !is_interrupted() {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(url) = urls.get(index) else {
+                        break;
+                    };
+                    let entries = expand_url(url, opts);
+                    if let Ok(mut result) = results[index].lock() {
+                        *result = Some(entries);
+                    }
+                }
+            });
+        }
+    });
+    results
+        .into_iter()
+        .map(|result| {
+            result
+                .into_inner()
+                .ok()
+                .flatten()
+                .unwrap_or_else(|| Err(anyhow!("Interrupted")))
+        })
+        .collect()
+}
+
+/// The selected entries of a `--flat-playlist -J` playlist.
+#[must_use]
+pub fn playlist_entries(playlist: &Value, opts: &PlaylistOptions) -> Vec<PlaylistEntry> {
+    let str_field =
+        |v: &Value, key: &str| v.get(key).and_then(Value::as_str).map(ToOwned::to_owned);
+    let entries = playlist
+        .get("entries")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let count = u64::try_from(entries.len()).unwrap_or(u64::MAX);
+    let title = str_field(playlist, "title");
+    let id = str_field(playlist, "id");
+    let uploader = str_field(playlist, "uploader").or_else(|| str_field(playlist, "channel"));
+
+    let selected = entries.iter().zip(1..).filter_map(|(entry, index)| {
+        if opts
+            .items
+            .as_ref()
+            .is_some_and(|items| !items.contains(index))
+        {
+            return None;
+        }
+        if let Some(date) = entry.get("upload_date").and_then(Value::as_str)
+            && !opts.dates.contains(date)
+        {
+            return None;
+        }
+        let url = str_field(entry, "url").or_else(|| str_field(entry, "webpage_url"))?;
+        Some(PlaylistEntry {
+            url,
+            position: Some(PlaylistPosition {
+                index,
+                count,
+                id: id.clone(),
+                title: title.clone(),
+                uploader: uploader.clone(),
+            }),
+        })
+    });
+    selected
+        .take(opts.max_items.unwrap_or(usize::MAX))
+        .collect()
+}
+
+fn single(url: &str) -> PlaylistEntry {
+    PlaylistEntry {
+        url: url.to_string(),
+        position: None,
+    }
+}
+
+/// URLs known to be a single `YouTube` video, which need no listing.
+fn is_video_url(url: &str) -> bool {
+    let path = path_of(url);
+    match host_of(url).as_str() {
+        "youtu.be" => true,
+        // Downloads pass --no-playlist, so `watch?v=..&list=..` is one video too
+        "youtube.com" | "m.youtube.com" | "music.youtube.com" => {
+            path.starts_with("/watch") || path.starts_with("/shorts/") || path.starts_with("/live/")
+        }
+        _ => false,
+    }
+}
+
+/// Path and query of `url`, starting at the `/` after the host.
+fn path_of(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    rest.find('/').map_or("", |i| &rest[i..])
+}
+
+/// A bare `YouTube` channel URL lists its tabs, not its videos; point it at
+/// the videos tab instead.
+fn listing_url(url: &str) -> String {
+    let host = host_of(url);
+    if host != "youtube.com" && host != "m.youtube.com" {
+        return url.to_string();
+    }
+    let base = url
+        .split(['?', '#'])
+        .next()
+        .unwrap_or(url)
+        .trim_end_matches('/');
+    let segments: Vec<&str> = path_of(base).split('/').filter(|s| !s.is_empty()).collect();
+    let is_channel = match segments.as_slice() {
+        [handle] => handle.starts_with('@'),
+        ["channel" | "c" | "user", _] => true,
+        _ => false,
+    };
+    if is_channel {
+        format!("{base}/videos")
+    } else {
+        url.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use serde_json::json;
+
+    #[rstest]
+    #[case("1-3,7,10-", &[1, 2, 3, 7, 10, 42], &[4, 8, 9])]
+    #[case("5", &[5], &[4, 6])]
+    fn test_playlist_items(#[case] spec: &str, #[case] yes: &[u64], #[case] no: &[u64]) {
+        let items: PlaylistItems = spec.parse().unwrap();
+        assert_eq!(items.to_string(), spec);
+        assert!(yes.iter().all(|&i| items.contains(i)));
+        assert!(no.iter().all(|&i| !items.contains(i)));
+    }
+
+    #[rstest]
+    #[case("0")]
+    #[case("a-3")]
+    #[case("")]
+    fn test_playlist_items_invalid(#[case] spec: &str) {
+        assert!(spec.parse::<PlaylistItems>().is_err());
+    }
+
+    #[rstest]
+    #[case("20240131", Some("20240131"))]
+    #[case("2024-01-31", Some("20240131"))]
+    #[case("2024-0131", None)]
+    #[case("yesterday", None)]
+    fn test_parse_date(#[case] input: &str, #[case] expected: Option<&str>) {
+        assert_eq!(parse_date(input).ok().as_deref(), expected);
+    }
+
+    #[test]
+    fn test_playlist_entries() {
+        let playlist = json!({
+            "_type": "playlist",
+            "id": "PL1",
+            "title": "Full Album",
+            "entries": [
+                {"url": "https://www.youtube.com/watch?v=a", "upload_date": "20230105"},
+                {"url": "https://www.youtube.com/watch?v=b", "upload_date": "20240105"},
+                {"url": "https://www.youtube.com/watch?v=c"},
+                {"url": "https://www.youtube.com/watch?v=d"},
+            ]
+        });
+        let opts = PlaylistOptions {
+            items: Some("2-".parse().unwrap()),
+            max_items: Some(2),
+            dates: DateRange {
+                after: Some("20240101".into()),
+                before: None,
+            },
+        };
+        let entries = playlist_entries(&playlist, &opts);
+        let urls: Vec<&str> = entries.iter().map(|e| e.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://www.youtube.com/watch?v=b",
+                "https://www.youtube.com/watch?v=c"
+            ]
+        );
+        let position = entries[1].position.as_ref().unwrap();
+        assert_eq!((position.index, position.count), (3, 4));
+        assert_eq!(position.title.as_deref(), Some("Full Album"));
+    }
+
+    #[rstest]
+    #[case("https://www.youtube.com/watch?v=abc", true)]
+    #[case("https://youtu.be/abc", true)]
+    #[case("https://www.youtube.com/watch?v=abc&list=PL1", true)]
+    #[case("https://www.youtube.com/playlist?list=PL1", false)]
+    #[case("https://example.bandcamp.com/album/x", false)]
+    fn test_is_video_url(#[case] url: &str, #[case] expected: bool) {
+        assert_eq!(is_video_url(url), expected);
+    }
+
+    #[rstest]
+    #[case(
+        "https://www.youtube.com/@artist",
+        "https://www.youtube.com/@artist/videos"
+    )]
+    #[case(
+        "https://www.youtube.com/channel/UC1/",
+        "https://www.youtube.com/channel/UC1/videos"
+    )]
+    #[case(
+        "https://www.youtube.com/@artist/streams",
+        "https://www.youtube.com/@artist/streams"
+    )]
+    #[case(
+        "https://www.youtube.com/playlist?list=PL1",
+        "https://www.youtube.com/playlist?list=PL1"
+    )]
+    fn test_listing_url(#[case] url: &str, #[case] expected: &str) {
+        assert_eq!(listing_url(url), expected);
+    }
+}
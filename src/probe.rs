@@ -0,0 +1,185 @@
+//! Reading duration, tags and embedded chapters of media files via `ffprobe`.
+
+use std::{collections::HashMap, path::Path, process::Command};
+
+use anyhow::{Context, Result, bail};
+use serde_json::Value;
+
+use crate::{chapters::Chapter, metadata::VideoInfo, process::run_output};
+
+/// What `ffprobe` reports about a media file.
+#[derive(Debug, Clone, Default)]
+pub struct MediaProbe {
+    /// Total length in seconds
+    pub duration: Option<f64>,
+    /// Chapters stored in the container
+    pub chapters: Vec<Chapter>,
+    /// Container tags with lowercased keys
+    pub tags: HashMap<String, String>,
+    /// Codec of the first audio stream, e.g. `opus` or `aac`
+    pub audio_codec: Option<String>,
+}
+
+impl MediaProbe {
+    /// Maps container tags onto the fields used for naming and tagging.
+    ///
+    /// The title falls back to the file name without extension.
+    #[must_use]
+    pub fn video_info(&self, path: &Path) -> VideoInfo {
+        let tag = |key: &str| self.tags.get(key).cloned();
+        VideoInfo {
+            title: tag("album").or_else(|| tag("title")).or_else(|| {
+                path.file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+            }),
+            uploader: tag("album_artist").or_else(|| tag("artist")),
+            artist: tag("artist"),
+            upload_date: tag("date"),
+            ..VideoInfo::default()
+        }
+    }
+}
+
+/// Runs `ffprobe` on `path`.
+///
+/// # Errors
+/// Fails when `ffprobe` cannot run or the file is not a readable media file.
+pub fn probe_media(path: &Path) -> Result<MediaProbe> {
+    let mut cmd = Command::new("ffprobe");
+    cmd.args([
+        "-v",
+        "error",
+        "-print_format",
+        "json",
+        "-show_format",
+        "-show_chapters",
+        "-show_streams",
+        "-select_streams",
+        "a:0",
+    ])
+    .arg(path);
+    let output = run_output(&mut cmd).context("Failed to execute ffprobe")?;
+
+    if !output.status.success() {
+        bail!(
+            "ffprobe failed on {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let json: Value =
+        serde_json::from_slice(&output.stdout).context("Invalid JSON from ffprobe")?;
+    Ok(parse_probe(&json))
+}
+
+/// Reads the chapters stored in the container of `path`.
+///
+/// # Errors
+/// Fails when `ffprobe` fails.
+pub fn probe_chapters(path: &Path) -> Result<Vec<Chapter>> {
+    Ok(probe_media(path)?.chapters)
+}
+
+fn parse_probe(json: &Value) -> MediaProbe {
+    let format = json.get("format");
+    let duration = format.and_then(|f| number(f.get("duration")));
+    let tags = format
+        .and_then(|f| f.get("tags"))
+        .and_then(Value::as_object)
+        .map(|tags| {
+            tags.iter()
+                .filter_map(|(k, v)| Some((k.to_ascii_lowercase(), v.as_str()?.to_string())))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let chapters = json
+        .get("chapters")
+        .and_then(Value::as_array)
+        .map(|list| {
+            list.iter()
+                .enumerate()
+                .filter_map(|(i, ch)| {
+                    let start_time = number(ch.get("start_time"))?;
+                    let end_time = number(ch.get("end_time"))?;
+                    let title = ch
+                        .get("tags")
+                        .and_then(Value::as_object)
+                        .and_then(|tags| {
+                            tags.iter()
+                                .find(|(k, _)| k.eq_ignore_ascii_case("title"))
+                                .and_then(|(_, v)| v.as_str())
+                        })
+                        .map_or_else(|| format!("Chapter {}", i + 1), ToOwned::to_owned);
+                    Some(Chapter {
+                        title,
+                        start_time,
+                        end_time,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let audio_codec = json
+        .get("streams")
+        .and_then(Value::as_array)
+        .and_then(|streams| streams.first())
+        .and_then(|stream| stream.get("codec_name"))
+        .and_then(Value::as_str)
+        .map(ToOwned::to_owned);
+
+    MediaProbe {
+        duration,
+        chapters,
+        tags,
+        audio_codec,
+    }
+}
+
+/// ffprobe prints most numbers as strings.
+fn number(value: Option<&Value>) -> Option<f64> {
+    match value? {
+        Value::String(s) => s.parse().ok(),
+        other => other.as_f64(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_probe() {
+        let json = serde_json::json!({
+            "chapters": [
+                {"id": 0, "start_time": "0.000000", "end_time": "95.500000", "tags": {"title": "Intro"}},
+                {"id": 1, "start_time": "95.500000", "end_time": "200.000000", "tags": {}}
+            ],
+            "streams": [{"index": 0, "codec_name": "opus", "codec_type": "audio"}],
+            "format": {"duration": "200.000000", "tags": {"ARTIST": "Someone", "title": "Live"}}
+        });
+        let probe = parse_probe(&json);
+        assert_eq!(probe.duration, Some(200.0));
+        assert_eq!(probe.audio_codec.as_deref(), Some("opus"));
+        assert_eq!(
+            probe.chapters,
+            vec![
+                Chapter {
+                    title: "Intro".into(),
+                    start_time: 0.0,
+                    end_time: 95.5
+                },
+                Chapter {
+                    title: "Chapter 2".into(),
+                    start_time: 95.5,
+                    end_time: 200.0
+                },
+            ]
+        );
+        let info = probe.video_info(Path::new("mix.flac"));
+        assert_eq!(info.title.as_deref(), Some("Live"));
+        assert_eq!(info.artist.as_deref(), Some("Someone"));
+    }
+}
@@ -0,0 +1,175 @@
+//! Helpers for running external tools.
+//!
+//! Every child is registered while it runs so an interrupt can stop it; see
+//! [`crate::interrupt`].
+
+use std::{
+    io::{BufRead, BufReader, Read},
+    process::{Child, Command, ExitStatus, Output, Stdio},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    thread,
+    time::Duration,
+};
+
+use anyhow::{Context, Result, bail};
+use indicatif::ProgressBar;
+
+use crate::interrupt::is_interrupted;
+
+/// Children that are still running.
+static CHILDREN: Mutex<Vec<Arc<Mutex<Child>>>> = Mutex::new(Vec::new());
+
+/// How often a waiting thread checks its child; waiting by polling leaves the
+/// child unlocked so an interrupt can kill it.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+pub(crate) fn run_command(cmd: &mut Command) -> Result<()> {
+    let child = spawn(cmd)?;
+    let status = wait(&child)?;
+    if status.success() {
+        Ok(())
+    } else {
+        bail!("Process exited with status: {status}")
+    }
+}
+
+/// Runs `cmd` to completion and collects its stdout and stderr.
+pub(crate) fn run_output(cmd: &mut Command) -> Result<Output> {
+    cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+    let child = spawn(cmd)?;
+    let (stdout, stderr) = {
+        let mut child = lock(&child);
+        (child.stdout.take(), child.stderr.take())
+    };
+    let out_handle = thread::spawn(move || read_all(stdout));
+    let err_handle = thread::spawn(move || read_all(stderr));
+
+    let status = wait(&child)?;
+    Ok(Output {
+        status,
+        stdout: out_handle.join().unwrap_or_default(),
+        stderr: err_handle.join().unwrap_or_default(),
+    })
+}
+
+pub(crate) fn run_streaming_lines(pb: &ProgressBar, cmd: &mut Command) -> Result<()> {
+    cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+    let child = spawn(cmd)?;
+
+    let (stdout, stderr) = {
+        let mut child = lock(&child);
+        (
+            child.stdout.take().context("Failed to capture stdout")?,
+            child.stderr.take().context("Failed to capture stderr")?,
+        )
+    };
+
+    let pb_out = pb.clone();
+    let out_handle = thread::spawn(move || {
+        let reader = BufReader::new(stdout);
+        for line in reader.lines().map_while(Result::ok) {
+            pb_out.println(line);
+        }
+    });
+
+    let pb_err = pb.clone();
+    let err_handle = thread::spawn(move || {
+        let reader = BufReader::new(stderr);
+        for line in reader.lines().map_while(Result::ok) {
+            pb_err.println(line);
+        }
+    });
+
+    let status = wait(&child)?;
+    let _ = out_handle.join();
+    let _ = err_handle.join();
+    if status.success() {
+        Ok(())
+    } else {
+        bail!("Process exited with status: {status}")
+    }
+}
+
+/// Starts `cmd` and registers the child; refuses once interrupted.
+pub(crate) fn spawn(cmd: &mut Command) -> Result<Arc<Mutex<Child>>> {
+    if is_interrupted() {
+        bail!("Interrupted");
+    }
+    let child = cmd
+        .spawn()
+        .with_context(|| format!("Failed to start {}", cmd.get_program().to_string_lossy()))?;
+    let child = Arc::new(Mutex::new(child));
+    if let Ok(mut children) = CHILDREN.lock() {
+        children.push(Arc::clone(&child));
+    }
+    // An interrupt between the check above and registering would miss this child
+    if is_interrupted() {
+        let _ = lock(&child).kill();
+    }
+    Ok(child)
+}
+
+/// Waits for a child started with [`spawn`] and unregisters it.
+pub(crate) fn wait(child: &Arc<Mutex<Child>>) -> Result<ExitStatus> {
+    let status = loop {
+        let polled = lock(child).try_wait();
+        match polled {
+            Ok(Some(status)) => break Ok(status),
+            Ok(None) => thread::sleep(POLL_INTERVAL),
+            Err(err) => break Err(err).context("Failed to wait for process"),
+        }
+    };
+    if let Ok(mut children) = CHILDREN.lock() {
+        children.retain(|c| !Arc::ptr_eq(c, child));
+    }
+    if is_interrupted() {
+        bail!("Interrupted");
+    }
+    status
+}
+
+/// Kills every registered child.
+pub(crate) fn kill_children() {
+    if let Ok(children) = CHILDREN.lock() {
+        for child in children.iter() {
+            let _ = lock(child).kill();
+        }
+    }
+}
+
+fn lock(child: &Mutex<Child>) -> MutexGuard<'_, Child> {
+    child.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn read_all(pipe: Option<impl Read>) -> Vec<u8> {
+    let mut buf = Vec::new();
+    if let Some(mut pipe) = pipe {
+        let _ = pipe.read_to_end(&mut buf);
+    }
+    buf
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::interrupt::{flag_guard, interrupt};
+
+    #[test]
+    fn test_spawn_refused_once_interrupted() {
+        let _flag = flag_guard();
+        interrupt();
+        let err = spawn(&mut Command::new("true")).unwrap_err();
+        assert_eq!(err.to_string(), "Interrupted");
+    }
+
+    #[test]
+    fn test_wait_fails_after_interrupt() {
+        let _flag = flag_guard();
+        let child = spawn(Command::new("sleep").arg("10")).unwrap();
+        interrupt();
+        let err = wait(&child).unwrap_err();
+        assert_eq!(err.to_string(), "Interrupted");
+        // The interrupt killed the child instead of letting it sleep
+        assert!(lock(&child).try_wait().unwrap().is_some());
+    }
+}
@@ -0,0 +1,95 @@
+//! Progress bar styles shared by the pipeline and the binary.
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+
+const COUNTER_TEMPLATE: &str =
+    "{spinner:.green} \x1b[90m{elapsed_precise}\x1b[0m [{bar:40.cyan/blue}] {pos}/{len} {msg}";
+const ELAPSED_SPINNER_TEMPLATE: &str = "{spinner:.green} \x1b[90m{elapsed_precise}\x1b[0m {msg}";
+
+/// Standalone spinner used outside of a `MultiProgress` (dependency installation).
+#[must_use]
+pub fn new_spinner(msg: &str) -> ProgressBar {
+    let pb = ProgressBar::new_spinner();
+    pb.enable_steady_tick(Duration::from_millis(100));
+    if let Ok(style) = ProgressStyle::with_template("{spinner:.green} {msg}") {
+        pb.set_style(style);
+    }
+    pb.set_message(msg.to_string());
+    pb
+}
+
+/// The bars of one job inside a shared `MultiProgress`.
+///
+/// A group created with [`ProgressGroup::under`] keeps its bars directly below
+/// its header, so concurrent jobs don't interleave their lines.
+#[derive(Debug, Clone)]
+pub struct ProgressGroup {
+    mp: MultiProgress,
+    last: Option<Arc<Mutex<ProgressBar>>>,
+}
+
+impl ProgressGroup {
+    /// Group whose bars are appended at the bottom of `mp`.
+    #[must_use]
+    pub fn new(mp: &MultiProgress) -> Self {
+        Self {
+            mp: mp.clone(),
+            last: None,
+        }
+    }
+
+    /// Group whose bars follow `header`, which must belong to `mp`.
+    #[must_use]
+    pub fn under(mp: &MultiProgress, header: &ProgressBar) -> Self {
+        Self {
+            mp: mp.clone(),
+            last: Some(Arc::new(Mutex::new(header.clone()))),
+        }
+    }
+
+    /// Adds `pb` after the group's latest bar.
+    pub fn add(&self, pb: ProgressBar) -> ProgressBar {
+        let Some(last) = &self.last else {
+            return self.mp.add(pb);
+        };
+        let Ok(mut last) = last.lock() else {
+            return self.mp.add(pb);
+        };
+        let pb = self.mp.insert_after(&last, pb);
+        *last = pb.clone();
+        pb
+    }
+
+    /// Prints `msg` above all bars.
+    pub fn println(&self, msg: impl AsRef<str>) {
+        let _ = self.mp.println(msg);
+    }
+}
+
+/// Spinner with elapsed time, attached to `mp`.
+#[must_use]
+pub fn add_spinner(mp: &ProgressGroup, msg: &str) -> ProgressBar {
+    let pb = mp.add(ProgressBar::new_spinner());
+    if let Ok(style) = ProgressStyle::with_template(ELAPSED_SPINNER_TEMPLATE) {
+        pb.set_style(style.progress_chars("#>-"));
+    }
+    pb.enable_steady_tick(Duration::from_millis(100));
+    pb.set_message(msg.to_string());
+    pb
+}
+
+/// Bar counting `len` items (`pos/len`), attached to `mp`.
+#[must_use]
+pub fn add_counter(mp: &ProgressGroup, len: u64, msg: &str) -> ProgressBar {
+    let pb = mp.add(ProgressBar::new(len));
+    if let Ok(style) = ProgressStyle::with_template(COUNTER_TEMPLATE) {
+        pb.set_style(style.progress_chars("#>-"));
+    }
+    pb.set_message(msg.to_string());
+    pb
+}
@@ -0,0 +1,875 @@
+//! Cutting the combined audio into tracks with `ffmpeg`.
+
+use std::{
+    collections::HashSet,
+    fmt, fs,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    process::Command,
+    str::FromStr,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    thread,
+};
+
+use anyhow::{Context, Result, bail};
+use indicatif::ProgressBar;
+
+use crate::{
+    chapters::Chapter,
+    cover::{cover_input_args, cover_output_args, supports_cover},
+    encode::{EncodeOptions, can_stream_copy},
+    interrupt::{PartialFiles, is_interrupted},
+    metadata::VideoInfo,
+    naming::Namer,
+    process::run_command,
+    progress::{ProgressGroup, add_counter, add_spinner},
+    tags::TrackTags,
+};
+
+/// Options for turning a combined audio file into tracks.
+#[derive(Debug, Clone)]
+pub struct SplitOptions {
+    /// Combined audio file to split
+    pub input: PathBuf,
+    /// Destination directory for split tracks
+    pub dest: Option<PathBuf>,
+    /// Chapters shorter than this many seconds are skipped
+    pub min_duration: f64,
+    /// Replace inherited tags with per-track title/album/artist/track/date/comment
+    pub tags: bool,
+    /// Image attached as front cover to every track whose format supports it
+    pub cover: Option<PathBuf>,
+    /// Re-encode each chapter so cuts land exactly on the chapter boundaries
+    /// instead of the nearest frame (`-c copy`)
+    pub accurate: bool,
+    /// Encoder settings used when re-encoding
+    pub encode: EncodeOptions,
+    /// Codec of the input's audio stream; decides which outputs can be
+    /// stream-copied. When unknown, only outputs sharing the input's
+    /// extension are copied.
+    pub source_codec: Option<String>,
+    /// Chapters cut at the same time; `0` uses one per CPU
+    pub jobs: usize,
+    /// What a failed chapter does to the remaining ones
+    pub on_error: ErrorPolicy,
+    /// What happens to tracks whose file already exists
+    pub overwrite: OverwritePolicy,
+    /// Tracks written by an earlier run; chapters whose outputs are all listed
+    /// here and still exist are not cut again
+    pub resume: HashSet<PathBuf>,
+    /// Called with each chapter's outputs once they are written
+    pub on_track: Option<TrackHook>,
+}
+
+type TrackFn = dyn Fn(&[PathBuf]) + Send + Sync;
+
+/// Callback receiving the files written for one chapter.
+#[derive(Clone)]
+pub struct TrackHook(Arc<TrackFn>);
+
+impl TrackHook {
+    pub fn new(hook: impl Fn(&[PathBuf]) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(hook))
+    }
+}
+
+impl fmt::Debug for TrackHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("TrackHook")
+    }
+}
+
+/// What [`Splitter::split`] does when a track's file already exists.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverwritePolicy {
+    /// Replace the existing file
+    #[default]
+    Overwrite,
+    /// Leave the existing file alone and don't write the track
+    Skip,
+    /// Write the track next to it as `name (2).ext`, `name (3).ext`, ...
+    Rename,
+}
+
+impl FromStr for OverwritePolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "overwrite" => Ok(Self::Overwrite),
+            "skip" => Ok(Self::Skip),
+            "rename" => Ok(Self::Rename),
+            _ => bail!("Unknown overwrite policy '{s}'; expected skip, overwrite or rename"),
+        }
+    }
+}
+
+impl fmt::Display for OverwritePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Overwrite => "overwrite",
+            Self::Skip => "skip",
+            Self::Rename => "rename",
+        })
+    }
+}
+
+/// Files produced by a split or conversion.
+#[derive(Debug, Clone, Default)]
+pub struct SplitReport {
+    /// Tracks written, in chapter order
+    pub written: Vec<PathBuf>,
+    /// Existing tracks left alone by [`OverwritePolicy::Skip`]
+    pub skipped: Vec<PathBuf>,
+}
+
+/// How [`Splitter::split`] reacts to a chapter that fails.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// Stop starting new chapters and return the first error
+    #[default]
+    Abort,
+    /// Cut every other chapter, then report how many failed
+    Continue,
+}
+
+impl SplitOptions {
+    /// Options for `input` with the default one second minimum chapter length.
+    #[must_use]
+    pub fn new(input: impl Into<PathBuf>) -> Self {
+        Self {
+            input: input.into(),
+            dest: None,
+            min_duration: 1.0,
+            tags: true,
+            cover: None,
+            accurate: false,
+            encode: EncodeOptions::default(),
+            source_codec: None,
+            jobs: 0,
+            on_error: ErrorPolicy::Abort,
+            overwrite: OverwritePolicy::Overwrite,
+            resume: HashSet::new(),
+            on_track: None,
+        }
+    }
+
+    /// Number of worker threads `jobs` resolves to.
+    #[must_use]
+    pub fn worker_count(&self) -> usize {
+        if self.jobs > 0 {
+            self.jobs
+        } else {
+            thread::available_parallelism().map_or(1, NonZeroUsize::get)
+        }
+    }
+}
+
+/// Splits or converts the combined audio file.
+#[derive(Debug, Clone)]
+pub struct Splitter {
+    opts: SplitOptions,
+}
+
+impl Splitter {
+    #[must_use]
+    pub fn new(opts: SplitOptions) -> Self {
+        Self { opts }
+    }
+
+    #[must_use]
+    pub fn options(&self) -> &SplitOptions {
+        &self.opts
+    }
+
+    /// Resolves `filename` against the destination directory.
+    #[must_use]
+    pub fn out_path(&self, filename: &str) -> PathBuf {
+        match &self.opts.dest {
+            Some(dir) => dir.join(filename),
+            None => PathBuf::from(filename),
+        }
+    }
+
+    /// Cuts one track per chapter, naming them with `namer`.
+    ///
+    /// # Errors
+    /// Fails when the destination cannot be created or `ffmpeg` fails on a chapter.
+    pub fn split(
+        &self,
+        mp: &ProgressGroup,
+        chapters: &[Chapter],
+        namer: &Namer,
+        info: &VideoInfo,
+    ) -> Result<SplitReport> {
+        self.split_to(mp, chapters, std::slice::from_ref(namer), info)
+    }
+
+    /// Cuts each chapter once and writes one track per namer, e.g. a FLAC and
+    /// an MP3 copy, from a single `ffmpeg` run.
+    ///
+    /// # Errors
+    /// Fails when the destination cannot be created or `ffmpeg` fails on a chapter.
+    pub fn split_to(
+        &self,
+        mp: &ProgressGroup,
+        chapters: &[Chapter],
+        namers: &[Namer],
+        info: &VideoInfo,
+    ) -> Result<SplitReport> {
+        let total = u64::try_from(chapters.len()).unwrap_or(u64::MAX);
+        let split_bar = add_counter(mp, total, "Splitting audio");
+
+        self.create_dest()?;
+
+        let CutPlan {
+            cuts,
+            skipped,
+            resumed,
+        } = self.plan_cuts(mp, chapters, namers, info)?;
+        if resumed > 0 {
+            split_bar.println(format!(
+                "\x1b[90mResuming: {resumed} of {} chapters already split\x1b[0m",
+                chapters.len()
+            ));
+        }
+        split_bar.inc(u64::try_from(chapters.len() - cuts.len()).unwrap_or(u64::MAX));
+
+        let outcome = run_pool(
+            &cuts,
+            self.opts.worker_count(),
+            self.opts.on_error,
+            |(index, ch, out_paths)| {
+                let result =
+                    self.split_chapter(&split_bar, *index, ch, out_paths, chapters.len(), info);
+                if let Err(err) = &result
+                    && self.opts.on_error == ErrorPolicy::Continue
+                    && !is_interrupted()
+                {
+                    split_bar.println(format!("\x1b[31m{err:#}\x1b[0m"));
+                }
+                split_bar.inc(1);
+                result
+            },
+        );
+        split_bar.finish_and_clear();
+
+        if is_interrupted() {
+            bail!(
+                "Interrupted after splitting {} of {} chapters",
+                chapters.len() - cuts.len() + outcome.done.len(),
+                chapters.len()
+            );
+        }
+        let failed = outcome.failures.len();
+        match outcome.failures.into_iter().next() {
+            None => {}
+            Some((_, err)) if self.opts.on_error == ErrorPolicy::Abort => return Err(err),
+            Some(_) => bail!("{failed} of {} chapters failed to split", chapters.len()),
+        }
+
+        Ok(SplitReport {
+            written: outcome
+                .done
+                .into_iter()
+                .filter(|(_, wrote)| *wrote)
+                .flat_map(|(position, _)| cuts[position].2.clone())
+                .collect(),
+            skipped,
+        })
+    }
+
+    /// Names each chapter's outputs and applies the overwrite policy, in
+    /// chapter order, so names don't depend on how the cuts are scheduled.
+    /// Chapters already split by an earlier run and chapters with no outputs
+    /// left are dropped.
+    fn plan_cuts<'a>(
+        &self,
+        mp: &ProgressGroup,
+        chapters: &'a [Chapter],
+        namers: &[Namer],
+        info: &VideoInfo,
+    ) -> Result<CutPlan<'a>> {
+        let mut plan = CutPlan {
+            cuts: Vec::with_capacity(chapters.len()),
+            skipped: Vec::new(),
+            resumed: 0,
+        };
+        let mut planned = HashSet::new();
+        for (index, ch) in chapters.iter().enumerate() {
+            let out_paths = namers
+                .iter()
+                .map(|namer| {
+                    let filename = namer.chapter_filename(index, chapters.len(), &ch.title, info);
+                    let out_path = self.out_path(&filename);
+                    create_parent(&out_path)?;
+                    Ok(out_path)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            if out_paths
+                .iter()
+                .all(|p| self.opts.resume.contains(p) && p.is_file())
+            {
+                plan.resumed += 1;
+                continue;
+            }
+            let out_paths = self.plan_outputs(mp, out_paths, &mut planned, &mut plan.skipped);
+            if !out_paths.is_empty() {
+                plan.cuts.push((index, ch, out_paths));
+            }
+        }
+        Ok(plan)
+    }
+
+    /// Applies the overwrite policy to one chapter's outputs, dropping or
+    /// renaming the ones that already exist.
+    fn plan_outputs(
+        &self,
+        mp: &ProgressGroup,
+        out_paths: Vec<PathBuf>,
+        planned: &mut HashSet<PathBuf>,
+        skipped: &mut Vec<PathBuf>,
+    ) -> Vec<PathBuf> {
+        let mut planned_paths = Vec::with_capacity(out_paths.len());
+        for path in out_paths {
+            let path = match self.opts.overwrite {
+                OverwritePolicy::Overwrite => path,
+                OverwritePolicy::Skip if path.exists() => {
+                    mp.println(format!(
+                        "\x1b[90mSkipping '{}' (already exists)\x1b[0m",
+                        path.display()
+                    ));
+                    skipped.push(path);
+                    continue;
+                }
+                OverwritePolicy::Skip => path,
+                OverwritePolicy::Rename => {
+                    let mut candidate = path.clone();
+                    let mut n = 2;
+                    while candidate.exists() || planned.contains(&candidate) {
+                        candidate = renamed_path(&path, n);
+                        n += 1;
+                    }
+                    candidate
+                }
+            };
+            planned.insert(path.clone());
+            planned_paths.push(path);
+        }
+        planned_paths
+    }
+
+    fn split_chapter(
+        &self,
+        split_bar: &ProgressBar,
+        index: usize,
+        ch: &Chapter,
+        out_paths: &[PathBuf],
+        total: usize,
+        info: &VideoInfo,
+    ) -> Result<bool> {
+        let start = ch.start_time.max(0.0);
+        // An infinite end (unknown total length) means "until the end of the input"
+        let duration = (!ch.end_time.is_infinite()).then(|| (ch.end_time - ch.start_time).max(0.0));
+        if duration.is_some_and(|d| !d.is_finite() || d < self.opts.min_duration) {
+            split_bar.println(format!(
+                "\x1b[90mSkipping '{}' (<{}s duration)\x1b[0m",
+                ch.title, self.opts.min_duration
+            ));
+            return Ok(false);
+        }
+        let tags = self
+            .opts
+            .tags
+            .then(|| TrackTags::for_chapter(info, index, total, &ch.title));
+        self.cut(start, duration, out_paths, tags.as_ref())
+            .with_context(|| format!("ffmpeg failed to split '{}'", ch.title))?;
+        self.track_done(out_paths);
+        Ok(true)
+    }
+
+    fn track_done(&self, out_paths: &[PathBuf]) {
+        if let Some(TrackHook(hook)) = &self.opts.on_track {
+            hook(out_paths);
+        }
+    }
+
+    /// Converts the whole input into a single track named after the video.
+    ///
+    /// # Errors
+    /// Fails when the destination cannot be created or `ffmpeg` fails.
+    pub fn convert_whole(
+        &self,
+        mp: &ProgressGroup,
+        namer: &Namer,
+        info: &VideoInfo,
+    ) -> Result<SplitReport> {
+        self.convert_whole_to(mp, std::slice::from_ref(namer), info)
+    }
+
+    /// Converts the whole input into one track per namer in a single `ffmpeg` run.
+    ///
+    /// # Errors
+    /// Fails when the destination cannot be created or `ffmpeg` fails.
+    pub fn convert_whole_to(
+        &self,
+        mp: &ProgressGroup,
+        namers: &[Namer],
+        info: &VideoInfo,
+    ) -> Result<SplitReport> {
+        let tags = self.opts.tags.then(|| TrackTags::for_video(info));
+        self.convert(mp, namers, |namer| namer.single_filename(info), tags)
+    }
+
+    /// Converts the whole input into one track of an album made from the
+    /// video's playlist, numbered by playlist position.
+    ///
+    /// # Errors
+    /// Fails when the destination cannot be created or `ffmpeg` fails.
+    pub fn convert_playlist_track_to(
+        &self,
+        mp: &ProgressGroup,
+        namers: &[Namer],
+        info: &VideoInfo,
+    ) -> Result<SplitReport> {
+        let tags = self.opts.tags.then(|| TrackTags::for_playlist_track(info));
+        self.convert(
+            mp,
+            namers,
+            |namer| namer.playlist_track_filename(info),
+            tags,
+        )
+    }
+
+    fn convert(
+        &self,
+        mp: &ProgressGroup,
+        namers: &[Namer],
+        filename: impl Fn(&Namer) -> String,
+        tags: Option<TrackTags>,
+    ) -> Result<SplitReport> {
+        self.create_dest()?;
+
+        let out_paths = namers
+            .iter()
+            .map(|namer| {
+                let out_path = self.out_path(&filename(namer));
+                create_parent(&out_path)?;
+                Ok(out_path)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mut skipped = Vec::new();
+        let out_paths = self.plan_outputs(mp, out_paths, &mut HashSet::new(), &mut skipped);
+        if out_paths.is_empty() {
+            return Ok(SplitReport {
+                written: Vec::new(),
+                skipped,
+            });
+        }
+
+        // Compatible codec and no explicit encoder settings: remux instead of transcoding
+        let remux = |out_path: &Path| !self.opts.encode.is_explicit() && self.can_copy_to(out_path);
+        let convert_bar = if out_paths.iter().all(|p| remux(p)) {
+            add_spinner(mp, "Copying entire video")
+        } else {
+            let formats: Vec<_> = out_paths
+                .iter()
+                .map(|p| extension(p).to_ascii_uppercase())
+                .collect();
+            add_spinner(
+                mp,
+                &format!("Converting entire video to {}", formats.join(", ")),
+            )
+        };
+
+        // Convert entire file without splitting
+        let mut cmd = Command::new("ffmpeg");
+        cmd.args([
+            "-hide_banner",
+            "-loglevel",
+            "error",
+            "-y",
+            "-i",
+            &self.opts.input.to_string_lossy(),
+        ]);
+        self.add_cover_input(&mut cmd, &out_paths);
+        for out_path in &out_paths {
+            self.add_output(&mut cmd, out_path, remux(out_path), tags.as_ref());
+        }
+        let partial = PartialFiles::new(out_paths.clone());
+        run_command(&mut cmd).context("ffmpeg failed to convert entire video")?;
+        partial.finish();
+        self.track_done(&out_paths);
+
+        convert_bar.finish_and_clear();
+        Ok(SplitReport {
+            written: out_paths,
+            skipped,
+        })
+    }
+
+    fn cut(
+        &self,
+        start: f64,
+        duration: Option<f64>,
+        out_paths: &[PathBuf],
+        tags: Option<&TrackTags>,
+    ) -> Result<()> {
+        let mut cmd = Command::new("ffmpeg");
+        cmd.args([
+            "-hide_banner",
+            "-loglevel",
+            "error",
+            "-y",
+            "-ss",
+            &format!("{start:.3}"),
+        ]);
+        if let Some(duration) = duration {
+            cmd.args(["-t", &format!("{duration:.3}")]);
+        }
+        cmd.arg("-i").arg(&self.opts.input);
+        self.add_cover_input(&mut cmd, out_paths);
+        for out_path in out_paths {
+            // Input seeking while transcoding decodes and discards up to the exact start
+            let copy = !self.opts.accurate && self.can_copy_to(out_path);
+            self.add_output(&mut cmd, out_path, copy, tags);
+        }
+        let partial = PartialFiles::new(out_paths.to_vec());
+        run_command(&mut cmd)?;
+        partial.finish();
+        Ok(())
+    }
+
+    /// Adds the cover as second input when any output can carry it.
+    fn add_cover_input(&self, cmd: &mut Command, out_paths: &[PathBuf]) {
+        if let Some(cover) = &self.opts.cover
+            && out_paths.iter().any(|p| supports_cover(p))
+        {
+            cmd.args(cover_input_args(cover));
+        }
+    }
+
+    fn add_output(&self, cmd: &mut Command, out_path: &Path, copy: bool, tags: Option<&TrackTags>) {
+        if self.opts.cover.is_some() {
+            cmd.args(cover_output_args(out_path));
+        }
+        if copy {
+            cmd.args(["-c", "copy"]);
+        } else {
+            cmd.args(self.opts.encode.ffmpeg_args(&extension(out_path)));
+        }
+        if let Some(tags) = tags {
+            cmd.args(tags.ffmpeg_args());
+        }
+        cmd.arg(out_path);
+    }
+
+    /// Whether the input's audio can be stream-copied into `out_path`.
+    fn can_copy_to(&self, out_path: &Path) -> bool {
+        let ext = extension(out_path);
+        match &self.opts.source_codec {
+            Some(codec) => can_stream_copy(codec, &ext),
+            None => extension(&self.opts.input).eq_ignore_ascii_case(&ext),
+        }
+    }
+
+    fn create_dest(&self) -> Result<()> {
+        if let Some(ref dest_dir) = self.opts.dest {
+            fs::create_dir_all(dest_dir).context("Failed to create destination directory")?;
+        }
+        Ok(())
+    }
+}
+
+/// `dir/name.ext` → `dir/name (n).ext`.
+fn renamed_path(path: &Path, n: usize) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let name = match path.extension() {
+        Some(ext) => format!("{stem} ({n}).{}", ext.to_string_lossy()),
+        None => format!("{stem} ({n})"),
+    };
+    path.with_file_name(name)
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .map(|e| e.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// A chapter to cut: its index, the chapter and the files to write.
+type Cut<'a> = (usize, &'a Chapter, Vec<PathBuf>);
+
+/// Chapters to cut after naming, see [`Splitter::plan_cuts`].
+struct CutPlan<'a> {
+    cuts: Vec<Cut<'a>>,
+    /// Existing tracks left alone by [`OverwritePolicy::Skip`]
+    skipped: Vec<PathBuf>,
+    /// Chapters already split by an earlier run
+    resumed: usize,
+}
+
+/// Results of [`run_pool`], each keyed and sorted by the item's position.
+struct PoolOutcome<T> {
+    done: Vec<(usize, T)>,
+    failures: Vec<(usize, anyhow::Error)>,
+}
+
+/// Runs `work` on `items` with up to `workers` threads, handing items out in
+/// order. With [`ErrorPolicy::Abort`] no item is started after a failure; a
+/// Ctrl-C stops handing out items either way.
+fn run_pool<I, T>(
+    items: &[I],
+    workers: usize,
+    on_error: ErrorPolicy,
+    work: impl Fn(&I) -> Result<T> + Sync,
+) -> PoolOutcome<T>
+where
+    I: Sync,
+    T: Send,
+{
+    let next = AtomicUsize::new(0);
+    let aborted = AtomicBool::new(false);
+    let done = Mutex::new(Vec::new());
+    let failures = Mutex::new(Vec::new());
+    thread::scope(|scope| {
+        for _ in 0..workers.min(items.len()) {
+            scope.spawn(|| {
+                while !aborted.load(Ordering::Relaxed) && !is_interrupted() {
+                    let position = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(item) = items.get(position) else {
+                        break;
+                    };
+                    match work(item) {
+                        Ok(value) => {
+                            if let Ok(mut done) = done.lock() {
+                                done.push((position, value));
+                            }
+                        }
+                        Err(err) => {
+                            if on_error == ErrorPolicy::Abort {
+                                aborted.store(true, Ordering::Relaxed);
+                            }
+                            if let Ok(mut failures) = failures.lock() {
+                                failures.push((position, err));
+                            }
+                        }
+                    }
+                }
+            });
+        }
+    });
+
+    let mut done = done.into_inner().unwrap_or_default();
+    let mut failures = failures.into_inner().unwrap_or_default();
+    done.sort_by_key(|(position, _)| *position);
+    failures.sort_by_key(|(position, _)| *position);
+    PoolOutcome { done, failures }
+}
+
+/// Creates the subdirectories a templated filename points into.
+fn create_parent(path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{interrupt::flag_guard, naming::NamingOptions};
+
+    #[test]
+    fn test_renamed_path() {
+        assert_eq!(
+            renamed_path(Path::new("tracks/01 Intro.mp3"), 2),
+            Path::new("tracks/01 Intro (2).mp3")
+        );
+    }
+
+    #[test]
+    fn test_plan_outputs_rename_avoids_planned() {
+        let splitter = Splitter::new(SplitOptions {
+            overwrite: OverwritePolicy::Rename,
+            ..SplitOptions::new("in.mp3")
+        });
+        let mp = ProgressGroup::new(&indicatif::MultiProgress::new());
+        let mut planned = HashSet::new();
+        let mut skipped = Vec::new();
+        let path = PathBuf::from("slycer-test-does-not-exist.mp3");
+        let first = splitter.plan_outputs(&mp, vec![path.clone()], &mut planned, &mut skipped);
+        let second = splitter.plan_outputs(&mp, vec![path], &mut planned, &mut skipped);
+        assert_eq!(first, [PathBuf::from("slycer-test-does-not-exist.mp3")]);
+        assert_eq!(
+            second,
+            [PathBuf::from("slycer-test-does-not-exist (2).mp3")]
+        );
+        assert!(skipped.is_empty());
+    }
+
+    #[test]
+    fn test_run_pool_abort_stops_handing_out_work() {
+        let _flag = flag_guard();
+        let started = AtomicUsize::new(0);
+        let items: Vec<usize> = (0..20).collect();
+        let outcome = run_pool(&items, 1, ErrorPolicy::Abort, |&i| {
+            started.fetch_add(1, Ordering::Relaxed);
+            if i == 3 {
+                bail!("chapter {i} failed")
+            } else {
+                Ok(i)
+            }
+        });
+        assert_eq!(started.load(Ordering::Relaxed), 4);
+        assert_eq!(outcome.done.len(), 3);
+        assert_eq!(outcome.failures.len(), 1);
+
+        // Workers busy when the first item fails finish it, but take no more
+        let started = AtomicUsize::new(0);
+        let outcome = run_pool(&items, 4, ErrorPolicy::Abort, |&i| {
+            started.fetch_add(1, Ordering::Relaxed);
+            if i == 0 {
+                bail!("chapter {i} failed");
+            }
+            thread::sleep(Duration::from_millis(20));
+            Ok(i)
+        });
+        assert!(started.load(Ordering::Relaxed) <= 4);
+        assert_eq!(outcome.failures[0].0, 0);
+    }
+
+    #[test]
+    fn test_run_pool_continue_collects_failures_in_order() {
+        let _flag = flag_guard();
+        let items: Vec<u64> = (0..10).collect();
+        // Later items finish first
+        let outcome = run_pool(&items, 4, ErrorPolicy::Continue, |&i| {
+            thread::sleep(Duration::from_millis(2 * (10 - i)));
+            if matches!(i, 2 | 5 | 7) {
+                bail!("chapter {i} failed")
+            } else {
+                Ok(i)
+            }
+        });
+        let failed: Vec<usize> = outcome.failures.iter().map(|(i, _)| *i).collect();
+        assert_eq!(failed, [2, 5, 7]);
+        assert_eq!(outcome.failures[1].1.to_string(), "chapter 5 failed");
+        let done: Vec<u64> = outcome.done.into_iter().map(|(_, i)| i).collect();
+        assert_eq!(done, [0, 1, 3, 4, 6, 8, 9]);
+    }
+
+    #[test]
+    fn test_plan_cuts_independent_of_jobs() {
+        let _flag = flag_guard();
+        let dest = std::env::temp_dir().join(format!("slycer-plan-{}", std::process::id()));
+        let chapter = |title: &str, start: f64| Chapter {
+            title: title.into(),
+            start_time: start,
+            end_time: start + 60.0,
+        };
+        let chapters = [
+            chapter("Intro", 0.0),
+            chapter("Song", 60.0),
+            chapter("Intro", 120.0),
+            chapter("Intro", 180.0),
+        ];
+        let namers = [Namer::new(NamingOptions::default(), "mp3")];
+        let mp = ProgressGroup::new(&indicatif::MultiProgress::new());
+        let expected: Vec<PathBuf> = ["Intro.mp3", "Song.mp3", "Intro (2).mp3", "Intro (3).mp3"]
+            .iter()
+            .map(|name| dest.join(name))
+            .collect();
+
+        for jobs in [1, 8] {
+            let splitter = Splitter::new(SplitOptions {
+                dest: Some(dest.clone()),
+                jobs,
+                overwrite: OverwritePolicy::Rename,
+                ..SplitOptions::new("in.mp3")
+            });
+            let plan = splitter
+                .plan_cuts(&mp, &chapters, &namers, &VideoInfo::default())
+                .unwrap();
+            let outcome = run_pool(
+                &plan.cuts,
+                splitter.options().worker_count(),
+                ErrorPolicy::Abort,
+                |(index, _, out_paths)| {
+                    // Reverse finishing order
+                    thread::sleep(Duration::from_millis(5 * (4 - *index as u64)));
+                    Ok(out_paths.clone())
+                },
+            );
+            let written: Vec<PathBuf> = outcome.done.into_iter().flat_map(|(_, p)| p).collect();
+            assert_eq!(written, expected, "jobs = {jobs}");
+        }
+        fs::remove_dir_all(&dest).unwrap();
+    }
+
+    #[test]
+    fn test_plan_cuts_resume() {
+        let dest = std::env::temp_dir().join(format!("slycer-resume-{}", std::process::id()));
+        fs::create_dir_all(&dest).unwrap();
+        let kept = dest.join("Intro.mp3");
+        let deleted = dest.join("Song.mp3");
+        fs::write(&kept, b"done").unwrap();
+        let chapters = [
+            Chapter {
+                title: "Intro".into(),
+                start_time: 0.0,
+                end_time: 60.0,
+            },
+            Chapter {
+                title: "Song".into(),
+                start_time: 60.0,
+                end_time: 120.0,
+            },
+            Chapter {
+                title: "Outro".into(),
+                start_time: 120.0,
+                end_time: 180.0,
+            },
+        ];
+        let splitter = Splitter::new(SplitOptions {
+            dest: Some(dest.clone()),
+            resume: [kept.clone(), deleted.clone()].into_iter().collect(),
+            ..SplitOptions::new("in.mp3")
+        });
+        let mp = ProgressGroup::new(&indicatif::MultiProgress::new());
+        let plan = splitter
+            .plan_cuts(
+                &mp,
+                &chapters,
+                &[Namer::new(NamingOptions::default(), "mp3")],
+                &VideoInfo::default(),
+            )
+            .unwrap();
+        fs::remove_dir_all(&dest).unwrap();
+
+        assert_eq!(plan.resumed, 1);
+        let cut: Vec<(usize, &[PathBuf])> = plan
+            .cuts
+            .iter()
+            .map(|(index, _, paths)| (*index, paths.as_slice()))
+            .collect();
+        assert_eq!(
+            cut,
+            [
+                (1, [deleted].as_slice()),
+                (2, [dest.join("Outro.mp3")].as_slice())
+            ]
+        );
+    }
+}
@@ -0,0 +1,177 @@
+//! Per-track metadata written by `ffmpeg` (ID3, MP4 atoms, Vorbis comments).
+
+use crate::metadata::VideoInfo;
+
+/// Tags for one output track. Empty fields are not written.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrackTags {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    /// One-based track number and track count
+    pub track: Option<(usize, usize)>,
+    pub date: Option<String>,
+    pub comment: Option<String>,
+}
+
+impl TrackTags {
+    /// Tags for chapter `index` (zero-based) out of `total`.
+    ///
+    /// A chapter titled `Artist - Title` sets its own artist; otherwise the
+    /// video's artist or uploader is used.
+    #[must_use]
+    pub fn for_chapter(info: &VideoInfo, index: usize, total: usize, chapter_title: &str) -> Self {
+        let video_artist = info.artist.clone().or_else(|| info.uploader.clone());
+        let (artist, title) = match split_artist_title(chapter_title) {
+            Some((artist, title)) => (Some(artist.to_string()), title.to_string()),
+            None => (video_artist.clone(), chapter_title.trim().to_string()),
+        };
+        Self {
+            title: Some(title),
+            artist,
+            album: info.title.clone(),
+            album_artist: video_artist,
+            track: Some((index + 1, total)),
+            date: info.upload_date.as_deref().map(format_date),
+            comment: info.webpage_url.clone(),
+        }
+    }
+
+    /// Tags for a video converted as a single track.
+    #[must_use]
+    pub fn for_video(info: &VideoInfo) -> Self {
+        let video_artist = info.artist.clone().or_else(|| info.uploader.clone());
+        let (artist, title) = match info.title.as_deref().and_then(split_artist_title) {
+            Some((artist, title)) => (Some(artist.to_string()), Some(title.to_string())),
+            None => (video_artist.clone(), info.title.clone()),
+        };
+        Self {
+            title,
+            artist,
+            album: info.title.clone(),
+            album_artist: video_artist,
+            track: None,
+            date: info.upload_date.as_deref().map(format_date),
+            comment: info.webpage_url.clone(),
+        }
+    }
+
+    /// Tags for a video converted as one track of an album made from its
+    /// playlist: numbered by playlist position, with the playlist as album.
+    #[must_use]
+    pub fn for_playlist_track(info: &VideoInfo) -> Self {
+        let video = Self::for_video(info);
+        Self {
+            album: info.playlist_title.clone().or(video.album),
+            album_artist: info.playlist_uploader.clone().or(video.album_artist),
+            track: info.playlist_position(),
+            ..video
+        }
+    }
+
+    /// `ffmpeg` arguments dropping the source's tags and writing these instead.
+    #[must_use]
+    pub fn ffmpeg_args(&self) -> Vec<String> {
+        let mut args = vec![
+            "-map_metadata".to_string(),
+            "-1".to_string(),
+            "-map_chapters".to_string(),
+            "-1".to_string(),
+        ];
+        let track = self.track.map(|(n, total)| format!("{n}/{total}"));
+        let fields = [
+            ("title", self.title.as_deref()),
+            ("artist", self.artist.as_deref()),
+            ("album", self.album.as_deref()),
+            ("album_artist", self.album_artist.as_deref()),
+            ("track", track.as_deref()),
+            ("date", self.date.as_deref()),
+            ("comment", self.comment.as_deref()),
+        ];
+        for (key, value) in fields {
+            if let Some(value) = value.filter(|v| !v.is_empty()) {
+                args.push("-metadata".to_string());
+                args.push(format!("{key}={value}"));
+            }
+        }
+        args
+    }
+}
+
+/// Splits `Artist - Title` (also with en/em dashes) into its parts.
+#[must_use]
+pub fn split_artist_title(title: &str) -> Option<(&str, &str)> {
+    [" - ", " – ", " — "].iter().find_map(|sep| {
+        let (artist, rest) = title.split_once(sep)?;
+        let (artist, rest) = (artist.trim(), rest.trim());
+        (!artist.is_empty() && !rest.is_empty()).then_some((artist, rest))
+    })
+}
+
+/// Turns yt-dlp's `YYYYMMDD` into `YYYY-MM-DD`; other values pass through.
+fn format_date(date: &str) -> String {
+    if date.len() == 8 && date.chars().all(|c| c.is_ascii_digit()) {
+        format!("{}-{}-{}", &date[..4], &date[4..6], &date[6..])
+    } else {
+        date.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("Daft Punk - One More Time", Some(("Daft Punk", "One More Time")))]
+    #[case("Boards of Canada – Roygbiv", Some(("Boards of Canada", "Roygbiv")))]
+    #[case("Intro", None)]
+    #[case("- Untitled", None)]
+    fn test_split_artist_title(#[case] input: &str, #[case] expected: Option<(&str, &str)>) {
+        assert_eq!(split_artist_title(input), expected);
+    }
+
+    #[test]
+    fn test_for_chapter() {
+        let info = VideoInfo {
+            title: Some("Summer Mix 2024".into()),
+            uploader: Some("DJ Someone".into()),
+            upload_date: Some("20240615".into()),
+            webpage_url: Some("https://www.youtube.com/watch?v=abc".into()),
+            ..VideoInfo::default()
+        };
+        let tags = TrackTags::for_chapter(&info, 1, 12, "Artist - Song");
+        assert_eq!(
+            tags,
+            TrackTags {
+                title: Some("Song".into()),
+                artist: Some("Artist".into()),
+                album: Some("Summer Mix 2024".into()),
+                album_artist: Some("DJ Someone".into()),
+                track: Some((2, 12)),
+                date: Some("2024-06-15".into()),
+                comment: Some("https://www.youtube.com/watch?v=abc".into()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_for_playlist_track() {
+        let info = VideoInfo {
+            title: Some("Artist - Song".into()),
+            uploader: Some("Artist - Topic".into()),
+            playlist_index: Some(4),
+            playlist_count: Some(11),
+            playlist_title: Some("Album".into()),
+            playlist_uploader: Some("Label".into()),
+            ..VideoInfo::default()
+        };
+        let tags = TrackTags::for_playlist_track(&info);
+        assert_eq!(tags.title.as_deref(), Some("Song"));
+        assert_eq!(tags.artist.as_deref(), Some("Artist"));
+        assert_eq!(tags.album.as_deref(), Some("Album"));
+        assert_eq!(tags.album_artist.as_deref(), Some("Label"));
+        assert_eq!(tags.track, Some((4, 11)));
+    }
+}
@@ -0,0 +1,249 @@
+//! Filename templates such as `{n:02} - {artist} - {chapter}.{ext}`.
+//!
+//! Placeholders are written in braces, optionally with a width (`{n:3}`) or a
+//! zero-padded width (`{n:02}`). `{{` and `}}` produce literal braces. A `/` in
+//! the template creates subdirectories; substituted values never do.
+
+use std::{fmt, str::FromStr};
+
+use anyhow::{Result, bail};
+
+/// Placeholder names accepted in templates.
+pub const FIELDS: &[&str] = &[
+    "n",
+    "total",
+    "chapter",
+    "video_title",
+    "title_prefix",
+    "uploader",
+    "artist",
+    "upload_date",
+    "id",
+    "playlist_index",
+    "playlist_title",
+    "ext",
+];
+
+/// Value substituted when a field is unknown for a video.
+const MISSING: &str = "NA";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Field {
+        name: String,
+        width: usize,
+        zero_pad: bool,
+    },
+}
+
+/// A parsed filename template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    source: String,
+    segments: Vec<Segment>,
+}
+
+impl Template {
+    /// Whether the file name (the part after the last `/`) references `{ext}`
+    /// itself; `{ext}` in a directory doesn't count.
+    #[must_use]
+    pub fn has_ext(&self) -> bool {
+        for segment in self.segments.iter().rev() {
+            match segment {
+                Segment::Field { name, .. } if name == "ext" => return true,
+                Segment::Literal(text) if text.contains('/') => return false,
+                _ => {}
+            }
+        }
+        false
+    }
+
+    /// Renders the template, looking placeholder values up with `value`.
+    ///
+    /// Values are made safe for use as a single path component; fields for
+    /// which `value` returns `None` render as `NA`.
+    pub fn render(&self, value: impl Fn(&str) -> Option<String>) -> String {
+        let mut out = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => out.push_str(text),
+                Segment::Field {
+                    name,
+                    width,
+                    zero_pad,
+                } => {
+                    let raw = value(name)
+                        .and_then(|v| sanitize_component(&v))
+                        .unwrap_or_else(|| MISSING.to_string());
+                    if *zero_pad {
+                        out.push_str(&format!("{raw:0>width$}"));
+                    } else {
+                        out.push_str(&format!("{raw:>width$}"));
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+impl fmt::Display for Template {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl FromStr for Template {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = s.chars().peekable();
+
+        while let Some(ch) = chars.next() {
+            match ch {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut inner = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => inner.push(c),
+                            None => bail!("Unclosed '{{' in template '{s}'"),
+                        }
+                    }
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(parse_field(&inner)?);
+                }
+                '}' => bail!("Unmatched '}}' in template '{s}' (use '}}}}' for a literal brace)"),
+                _ => literal.push(ch),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        if segments.is_empty() {
+            bail!("Template is empty");
+        }
+
+        Ok(Self {
+            source: s.to_string(),
+            segments,
+        })
+    }
+}
+
+fn parse_field(inner: &str) -> Result<Segment> {
+    let (name, spec) = match inner.split_once(':') {
+        Some((name, spec)) => (name.trim(), Some(spec.trim())),
+        None => (inner.trim(), None),
+    };
+    if !FIELDS.contains(&name) {
+        bail!(
+            "Unknown template placeholder '{{{name}}}'; expected one of: {}",
+            FIELDS.join(", ")
+        );
+    }
+    let (width, zero_pad) = match spec {
+        None | Some("") => (0, false),
+        Some(spec) => {
+            let zero_pad = spec.len() > 1 && spec.starts_with('0');
+            let Ok(width) = spec.parse::<usize>() else {
+                bail!("Invalid width '{spec}' for '{{{name}}}'; expected e.g. '{{{name}:02}}'");
+            };
+            (width, zero_pad)
+        }
+    };
+    Ok(Segment::Field {
+        name: name.to_string(),
+        width,
+        zero_pad,
+    })
+}
+
+/// Makes `value` usable as one path component: path separators and characters
+/// invalid on common filesystems become `_`, surrounding dots and spaces are trimmed.
+#[must_use]
+pub fn sanitize_component(value: &str) -> Option<String> {
+    let replaced: String = value
+        .chars()
+        .map(|ch| match ch {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => ' ',
+            c => c,
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let trimmed = replaced.trim_matches(|c: char| c == '.' || c == ' ');
+    if trimmed.is_empty() {
+        None
+    } else {
+        Some(trimmed.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "n" => Some("3".into()),
+            "chapter" => Some("Intro: Part 1/2".into()),
+            "uploader" => Some("Some Artist".into()),
+            "video_title" => Some("Live at ...".into()),
+            "ext" => Some("mp3".into()),
+            _ => None,
+        }
+    }
+
+    #[rstest]
+    #[case(
+        "{n:02} - {uploader} - {chapter}.{ext}",
+        "03 - Some Artist - Intro_ Part 1_2.mp3"
+    )]
+    #[case(
+        "{uploader}/{video_title}/{n} {chapter}",
+        "Some Artist/Live at/3 Intro_ Part 1_2"
+    )]
+    #[case("{n:3}|{id}", "  3|NA")]
+    #[case("{{{n}}}", "{3}")]
+    fn test_render(#[case] template: &str, #[case] expected: &str) {
+        let template: Template = template.parse().unwrap();
+        assert_eq!(template.render(lookup), expected);
+    }
+
+    #[rstest]
+    #[case("{n} {chapter}.{ext}", true)]
+    #[case("{ext}/{n:02} {chapter}", false)]
+    #[case("{ext}/{n:02} {chapter}.{ext}", true)]
+    #[case("{n} {chapter}", false)]
+    fn test_has_ext(#[case] template: &str, #[case] expected: bool) {
+        let template: Template = template.parse().unwrap();
+        assert_eq!(template.has_ext(), expected);
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("{n")]
+    #[case("n}")]
+    #[case("{nope}")]
+    #[case("{n:xx}")]
+    fn test_parse_invalid(#[case] template: &str) {
+        assert!(template.parse::<Template>().is_err());
+    }
+}
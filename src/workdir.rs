@@ -0,0 +1,122 @@
+//! Private per-job working directories for downloads and other temp files.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use anyhow::{Context, Result};
+
+use crate::template::sanitize_component;
+
+/// Directories of jobs still running, for cleanup on interruption.
+static ACTIVE: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Default parent for job directories: `slycer` in the system temp directory.
+#[must_use]
+pub fn default_work_root() -> PathBuf {
+    std::env::temp_dir().join("slycer")
+}
+
+/// A directory only one job writes to, removed with everything in it on drop.
+#[derive(Debug)]
+pub struct WorkDir {
+    path: PathBuf,
+}
+
+impl WorkDir {
+    /// Creates `<root>/<name>-<pid>-<n>`; unique across jobs and processes.
+    ///
+    /// # Errors
+    /// Fails when the directory cannot be created.
+    pub fn create(root: &Path, name: &str) -> Result<Self> {
+        let name = sanitize_component(name).unwrap_or_else(|| "job".to_string());
+        let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let path = root.join(format!("{name}-{}-{n}", process::id()));
+        fs::create_dir_all(&path)
+            .with_context(|| format!("Failed to create work directory {}", path.display()))?;
+        if let Ok(mut active) = ACTIVE.lock() {
+            active.push(path.clone());
+        }
+        Ok(Self { path })
+    }
+
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Path of `name` inside the directory.
+    #[must_use]
+    pub fn file(&self, name: &str) -> PathBuf {
+        self.path.join(name)
+    }
+}
+
+impl Drop for WorkDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+        if let Ok(mut active) = ACTIVE.lock() {
+            active.retain(|p| p != &self.path);
+        }
+    }
+}
+
+/// Removes the directories of all running jobs; meant for signal handlers,
+/// where the jobs' own cleanup never runs.
+pub fn remove_active_work_dirs() {
+    if let Ok(active) = ACTIVE.lock() {
+        for path in active.iter() {
+            let _ = fs::remove_dir_all(path);
+        }
+    }
+}
+
+/// Moves `from` to `to`, copying when they are on different filesystems.
+///
+/// # Errors
+/// Fails when neither renaming nor copying works.
+pub fn move_file(from: &Path, to: &Path) -> Result<()> {
+    if let Some(parent) = to.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+    }
+    let moved = fs::rename(from, to).or_else(|_| {
+        fs::copy(from, to)?;
+        fs::remove_file(from)
+    });
+    moved.with_context(|| format!("Failed to move {} to {}", from.display(), to.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_work_dir_lifecycle() {
+        let root = std::env::temp_dir().join("slycer-workdir-test");
+        let first = WorkDir::create(&root, "abc/def").unwrap();
+        let second = WorkDir::create(&root, "abc/def").unwrap();
+        assert_ne!(first.path(), second.path());
+        assert!(first.path().is_dir());
+
+        fs::write(first.file("a.mp3"), b"x").unwrap();
+        let kept = second.file("kept.mp3");
+        move_file(&first.file("a.mp3"), &kept).unwrap();
+        assert!(kept.is_file());
+
+        let path = first.path().to_path_buf();
+        drop(first);
+        assert!(!path.exists());
+        drop(second);
+        let _ = fs::remove_dir(&root);
+    }
+}